@@ -0,0 +1,128 @@
+//! Encoding command lines the way ViperServer splits them.
+//!
+//! ViperServer receives the command line of a verification as a single
+//! string, which it splits into arguments with the regex
+//! `[^\s"']+|"[^"]*"|'[^']*'` and then strips the quotes surrounding each
+//! argument with `^["'](.*)["']$`. There are no escapes, so an argument is
+//! quoted with whichever quote it does not contain itself. As `.` does not
+//! match line breaks, arguments cannot contain any.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::opts::{CarbonOpts, SiliconOpts, ViperServerOpts};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ArgsError {
+    #[error(
+        "{arg:?} contains both `\"` and `'`, which ViperServer cannot receive as one argument"
+    )]
+    Unrepresentable { arg: String },
+    #[error("{arg:?} contains a line break, which ViperServer cannot receive")]
+    LineBreak { arg: String },
+    #[error("the path {path:?} is not valid unicode")]
+    NonUnicodePath { path: std::path::PathBuf },
+}
+
+/// Whitespace as matched by `\s` in Java regexes.
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\x0B' | '\x0C' | '\r')
+}
+
+/// Line terminators as known to Java regexes, which `.` does not match.
+fn is_line_break(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{85}' | '\u{2028}' | '\u{2029}')
+}
+
+/// Quote `arg`, if needed, so that ViperServer receives it as one argument.
+pub fn quote(arg: &str) -> Result<String, ArgsError> {
+    if arg.contains(is_line_break) {
+        return Err(ArgsError::LineBreak {
+            arg: arg.to_string(),
+        });
+    }
+    let plain = |c: char| !(is_space(c) || c == '"' || c == '\'');
+    if !arg.is_empty() && arg.chars().all(plain) {
+        Ok(arg.to_string())
+    } else if !arg.contains('"') {
+        Ok(format!("\"{arg}\""))
+    } else if !arg.contains('\'') {
+        Ok(format!("'{arg}'"))
+    } else {
+        Err(ArgsError::Unrepresentable {
+            arg: arg.to_string(),
+        })
+    }
+}
+
+/// Join `args` into a command line that [`split`] turns back into `args`.
+pub fn encode<I>(args: I) -> Result<String, ArgsError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let args = args
+        .into_iter()
+        .map(|arg| quote(arg.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(args.join(" "))
+}
+
+/// Split a command line like ViperServer does.
+pub fn split(line: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if is_space(c) {
+            chars.next();
+            continue;
+        }
+        if c == '"' || c == '\'' {
+            chars.next();
+            // An unmatched quote is not part of any argument
+            if let Some(len) = line[start + 1..].find(c) {
+                let end = start + 1 + len;
+                args.push(line[start + 1..end].to_string());
+                while chars.next_if(|&(i, _)| i <= end).is_some() {}
+            }
+            continue;
+        }
+        let mut end = line.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if is_space(c) || c == '"' || c == '\'' {
+                end = i;
+                break;
+            }
+            chars.next();
+        }
+        args.push(line[start..end].to_string());
+    }
+    args
+}
+
+/// The path of a file to verify, as an argument.
+pub(crate) fn path_arg(path: &Path) -> Result<&str, ArgsError> {
+    path.to_str().ok_or_else(|| ArgsError::NonUnicodePath {
+        path: path.to_path_buf(),
+    })
+}
+
+/// Options that can be passed on a command line.
+pub trait ToArgs {
+    fn to_args(&self) -> Vec<String>;
+}
+
+macro_rules! impl_to_args {
+    ($($opts:ty),*) => {
+        $(impl ToArgs for $opts {
+            fn to_args(&self) -> Vec<String> {
+                let mut args = vec![];
+                self.apply(|arg| args.push(arg.to_string()));
+                args
+            }
+        })*
+    };
+}
+
+impl_to_args!(ViperServerOpts, SiliconOpts, CarbonOpts);
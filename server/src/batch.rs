@@ -0,0 +1,197 @@
+use std::{
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use derive_builder::Builder;
+use futures::{stream::BoxStream, Stream, StreamExt};
+
+use crate::{
+    client::{Client, VerificationRequest},
+    error::Result,
+    outcome::VerificationOutcome,
+    server::DEFAULT_MAXIMUM_ACTIVE_JOBS,
+};
+
+/// Settings for [`Client::verify_batch`].
+#[derive(Debug, Clone, Builder)]
+pub struct BatchConfig {
+    /// How many verifications to run at once. Never more than the maximum
+    /// number of active jobs of a server the client owns, which is also the
+    /// default.
+    #[builder(setter(into, strip_option), default)]
+    pub parallelism: Option<usize>,
+    /// How many of the slowest files the [`BatchSummary`] lists.
+    #[builder(default = "5")]
+    pub slowest: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfigBuilder::default()
+            .build()
+            .expect("all fields have defaults")
+    }
+}
+
+/// The result of one verification in a batch.
+#[derive(Debug)]
+pub struct BatchResult {
+    /// The position of the request in the batch.
+    pub index: usize,
+    pub file: PathBuf,
+    pub outcome: Result<VerificationOutcome>,
+    /// How long the verification took, including waiting for the server.
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchStatus {
+    Passed,
+    /// The program did not verify.
+    Failed,
+    /// The verification could not be carried out, because of a network error,
+    /// an exception in the server, invalid arguments or a cancellation.
+    Errored,
+}
+
+impl BatchResult {
+    pub fn status(&self) -> BatchStatus {
+        match &self.outcome {
+            Ok(outcome) if outcome.is_success() => BatchStatus::Passed,
+            Ok(outcome)
+                if outcome.exceptions.is_empty()
+                    && outcome.invalid_args.is_empty()
+                    && !outcome.cancelled =>
+            {
+                BatchStatus::Failed
+            }
+            _ => BatchStatus::Errored,
+        }
+    }
+}
+
+/// What happened to the verifications of a batch so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub errored: usize,
+    /// The time from starting the batch until the last result.
+    pub total_time: Duration,
+    /// The slowest files, slowest first.
+    pub slowest: Vec<(PathBuf, Duration)>,
+}
+
+impl BatchSummary {
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.errored
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && self.errored == 0
+    }
+
+    fn record(&mut self, result: &BatchResult, slowest: usize) {
+        match result.status() {
+            BatchStatus::Passed => self.passed += 1,
+            BatchStatus::Failed => self.failed += 1,
+            BatchStatus::Errored => self.errored += 1,
+        }
+        let at = self
+            .slowest
+            .partition_point(|(_, time)| *time >= result.time);
+        if at < slowest {
+            self.slowest.insert(at, (result.file.clone(), result.time));
+            self.slowest.truncate(slowest);
+        }
+    }
+}
+
+/// The results of [`Client::verify_batch`], in the order they finish.
+///
+/// The stream keeps a [`BatchSummary`] of the results it has yielded.
+pub struct Batch<'a> {
+    results: BoxStream<'a, BatchResult>,
+    summary: BatchSummary,
+    slowest: usize,
+    started: Instant,
+}
+
+impl Batch<'_> {
+    pub fn summary(&self) -> &BatchSummary {
+        &self.summary
+    }
+
+    /// Wait for the remaining verifications, and return the summary of the
+    /// whole batch.
+    pub async fn finish(mut self) -> BatchSummary {
+        while self.next().await.is_some() {}
+        self.summary
+    }
+}
+
+impl Stream for Batch<'_> {
+    type Item = BatchResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let res = this.results.as_mut().poll_next(cx);
+        if let Poll::Ready(Some(result)) = &res {
+            this.summary.record(result, this.slowest);
+            this.summary.total_time = this.started.elapsed();
+        }
+        res
+    }
+}
+
+impl std::fmt::Debug for Batch<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Batch")
+            .field("summary", &self.summary)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Client {
+    /// Verify `requests`, running as many at once as `config` and the server
+    /// allow.
+    pub fn verify_batch<'a, I>(&'a self, requests: I, config: &BatchConfig) -> Batch<'a>
+    where
+        I: IntoIterator<Item = VerificationRequest>,
+        I::IntoIter: Send + 'a,
+    {
+        // The limit of servers the client does not own is unknown, so the
+        // configured parallelism is trusted for those
+        let parallelism = match (&self.server, config.parallelism) {
+            (Some(server), Some(parallelism)) => parallelism.min(server.maximum_active_jobs()),
+            (Some(server), None) => server.maximum_active_jobs(),
+            (None, parallelism) => parallelism.unwrap_or(DEFAULT_MAXIMUM_ACTIVE_JOBS),
+        }
+        .max(1);
+
+        let results = futures::stream::iter(requests.into_iter().enumerate())
+            .map(move |(index, request)| async move {
+                let file = request.file().path().to_path_buf();
+                let started = Instant::now();
+                let outcome = self.verify(request).await;
+                BatchResult {
+                    index,
+                    file,
+                    outcome,
+                    time: started.elapsed(),
+                }
+            })
+            .buffer_unordered(parallelism)
+            .boxed();
+
+        Batch {
+            results,
+            summary: BatchSummary::default(),
+            slowest: config.slowest,
+            started: Instant::now(),
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//! Synchronous versions of [`ViperServer`](crate::ViperServer) and
+//! [`Client`](crate::Client), for code that does not run in an async context.
+//!
+//! They run on a runtime managed by this module. Calling them from within an
+//! async runtime panics, just like [`tokio::runtime::Runtime::block_on`].
+
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    sync::OnceLock,
+    time::Duration,
+};
+
+use futures::StreamExt;
+use reqwest::Url;
+use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    client::{self, MessageResponse, VerificationResponse, VerificationStream, ViperRequest},
+    error::Result,
+    http::HttpConfig,
+    jvm::JvmConfig,
+    opts::{ViperServerOpts, ViperServerOptsBuilder},
+    outcome::VerificationOutcome,
+    output::OutputLog,
+    server::{self, ServerConfig, ShutdownReport},
+    verification::Backend,
+};
+
+pub use crate::client::{VerificationRequest, VerificationStatus};
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("viperserver-blocking")
+            .enable_all()
+            .build()
+            .expect("failed to start the runtime for blocking calls")
+    })
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    runtime().block_on(future)
+}
+
+/// A synchronous [`crate::ViperServer`].
+#[derive(Debug)]
+pub struct ViperServer {
+    /// Only `None` while being dropped
+    inner: Option<server::ViperServer>,
+}
+
+impl ViperServer {
+    pub fn spawn(viper_server_jar: impl AsRef<Path>, opts: ViperServerOpts) -> Result<Self> {
+        Self::spawn_with(viper_server_jar, &JvmConfig::default(), opts)
+    }
+
+    pub fn spawn_with(
+        viper_server_jar: impl AsRef<Path>,
+        jvm: &JvmConfig,
+        opts: ViperServerOpts,
+    ) -> Result<Self> {
+        block_on(server::ViperServer::spawn_with(viper_server_jar, jvm, opts)).map(Self::from)
+    }
+
+    pub fn spawn_configured(
+        viper_server_jar: impl AsRef<Path>,
+        jvm: &JvmConfig,
+        config: &ServerConfig,
+        opts: ViperServerOpts,
+    ) -> Result<Self> {
+        block_on(server::ViperServer::spawn_configured(
+            viper_server_jar,
+            jvm,
+            config,
+            opts,
+        ))
+        .map(Self::from)
+    }
+
+    /// Spawn an HTTP server with the options from
+    /// [`crate::ViperServer::builder`].
+    pub fn spawn_http(
+        opts: &mut ViperServerOptsBuilder,
+        viper_server_jar: impl AsRef<Path>,
+    ) -> Result<Self> {
+        Self::spawn_http_with(opts, &JvmConfig::default(), viper_server_jar)
+    }
+
+    pub fn spawn_http_with(
+        opts: &mut ViperServerOptsBuilder,
+        jvm: &JvmConfig,
+        viper_server_jar: impl AsRef<Path>,
+    ) -> Result<Self> {
+        block_on(opts.spawn_http_with(jvm, viper_server_jar)).map(Self::from)
+    }
+
+    fn inner(&self) -> &server::ViperServer {
+        self.inner.as_ref().expect("only taken on drop")
+    }
+
+    fn inner_mut(&mut self) -> &mut server::ViperServer {
+        self.inner.as_mut().expect("only taken on drop")
+    }
+
+    /// See [`crate::ViperServer::online_at`].
+    pub fn online_at(&mut self) -> Result<String> {
+        block_on(self.inner_mut().online_at())
+    }
+
+    pub fn startup_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.inner_mut().startup_timeout(timeout);
+        self
+    }
+
+    pub fn id(&self) -> Option<u32> {
+        self.inner().id()
+    }
+
+    pub fn try_wait(&self) -> Result<Option<ExitStatus>> {
+        self.inner().try_wait()
+    }
+
+    pub fn wait(&self) -> Result<ExitStatus> {
+        block_on(self.inner().wait())
+    }
+
+    pub fn kill(&self) {
+        self.inner().kill()
+    }
+
+    pub fn stdout(&self) -> &OutputLog {
+        &self.inner().stdout
+    }
+
+    pub fn stderr(&self) -> &OutputLog {
+        &self.inner().stderr
+    }
+
+    /// See [`crate::ViperServer::shutdown_on_drop`].
+    pub fn shutdown_on_drop(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.inner_mut().shutdown_on_drop(timeout);
+        self
+    }
+
+    /// See [`crate::ViperServer::shutdown`].
+    pub fn shutdown(&mut self, timeout: Duration) -> Result<ShutdownReport> {
+        block_on(self.inner_mut().shutdown(timeout))
+    }
+
+    pub fn into_async(mut self) -> server::ViperServer {
+        self.inner.take().expect("only taken on drop")
+    }
+}
+
+impl From<server::ViperServer> for ViperServer {
+    fn from(server: server::ViperServer) -> Self {
+        ViperServer {
+            inner: Some(server),
+        }
+    }
+}
+
+impl Drop for ViperServer {
+    fn drop(&mut self) {
+        // A graceful shutdown on drop is spawned onto the current runtime
+        let _guard = runtime().enter();
+        self.inner.take();
+    }
+}
+
+/// A synchronous [`crate::Client`].
+#[derive(Debug)]
+pub struct Client {
+    /// Only `None` while being dropped
+    inner: Option<client::Client>,
+}
+
+impl Client {
+    pub fn new(server: ViperServer) -> Result<Client> {
+        block_on(client::Client::new(server.into_async())).map(Self::from)
+    }
+
+    pub fn new_with(server: ViperServer, http: &HttpConfig) -> Result<Client> {
+        block_on(client::Client::new_with(server.into_async(), http)).map(Self::from)
+    }
+
+    /// See [`crate::Client::connect`].
+    pub fn connect(url: impl AsRef<str>) -> Result<Client> {
+        block_on(client::Client::connect(url)).map(Self::from)
+    }
+
+    pub fn connect_with(url: impl AsRef<str>, http: &HttpConfig) -> Result<Client> {
+        block_on(client::Client::connect_with(url, http)).map(Self::from)
+    }
+
+    fn inner(&self) -> &client::Client {
+        self.inner.as_ref().expect("only taken on drop")
+    }
+
+    pub fn base_url(&self) -> &Url {
+        self.inner().base_url()
+    }
+
+    pub fn post<R: ViperRequest>(&self, body: R) -> Result<R::Response> {
+        block_on(self.inner().post(body))
+    }
+
+    pub fn check_on_verification(&self, v: &VerificationResponse) -> Result<VerificationIter> {
+        let stream = block_on(self.inner().check_on_verification(v))?;
+        Ok(VerificationIter {
+            stream: Some(stream),
+        })
+    }
+
+    /// See [`crate::Client::flush_cache`].
+    pub fn flush_cache(&self) -> Result<MessageResponse> {
+        block_on(self.inner().flush_cache())
+    }
+
+    /// See [`crate::Client::flush_file_cache`].
+    pub fn flush_file_cache(
+        &self,
+        backend: Backend,
+        file: impl Into<PathBuf>,
+    ) -> Result<MessageResponse> {
+        block_on(self.inner().flush_file_cache(backend, file))
+    }
+
+    /// See [`crate::Client::exit`].
+    pub fn exit(&self) -> Result<MessageResponse> {
+        block_on(self.inner().exit())
+    }
+
+    /// See [`crate::Client::cancel`].
+    pub fn cancel(&self, v: &VerificationResponse) -> Result<()> {
+        block_on(self.inner().cancel(v))
+    }
+
+    /// See [`crate::Client::cancel_on_drop`].
+    pub fn cancel_on_drop(&mut self, cancel: bool) -> &mut Self {
+        if let Some(inner) = &mut self.inner {
+            inner.cancel_on_drop(cancel);
+        }
+        self
+    }
+
+    /// Send `request` and return the statuses of the verification it starts.
+    pub fn verify_stream(&self, request: VerificationRequest) -> Result<VerificationIter> {
+        let response = self.post(request)?;
+        self.check_on_verification(&response)
+    }
+
+    /// See [`crate::Client::verify`].
+    pub fn verify(&self, request: VerificationRequest) -> Result<VerificationOutcome> {
+        block_on(self.inner().verify(request))
+    }
+
+    /// See [`crate::Client::shutdown_on_drop`].
+    pub fn shutdown_on_drop(&mut self, timeout: Option<Duration>) -> &mut Self {
+        if let Some(inner) = &mut self.inner {
+            inner.shutdown_on_drop(timeout);
+        }
+        self
+    }
+
+    /// See [`crate::Client::shutdown`].
+    pub fn shutdown(mut self, timeout: Duration) -> Result<Option<ShutdownReport>> {
+        let inner = self.inner.take().expect("only taken on drop");
+        block_on(inner.shutdown(timeout))
+    }
+
+    pub fn into_async(mut self) -> client::Client {
+        self.inner.take().expect("only taken on drop")
+    }
+}
+
+impl From<client::Client> for Client {
+    fn from(client: client::Client) -> Self {
+        Client {
+            inner: Some(client),
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _guard = runtime().enter();
+        self.inner.take();
+    }
+}
+
+/// The statuses of a verification, received as they are reported. See
+/// [`VerificationStream`].
+#[derive(Debug)]
+pub struct VerificationIter {
+    /// Only `None` while being dropped
+    stream: Option<VerificationStream>,
+}
+
+impl VerificationIter {
+    fn stream(&self) -> &VerificationStream {
+        self.stream.as_ref().expect("only taken on drop")
+    }
+
+    /// See [`VerificationStream::cancel`].
+    pub fn cancel(&self) {
+        self.stream().cancel()
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.stream().cancellation_token()
+    }
+
+    pub fn cancel_on_drop(&mut self, cancel: bool) -> &mut Self {
+        if let Some(stream) = &mut self.stream {
+            stream.cancel_on_drop(cancel);
+        }
+        self
+    }
+}
+
+impl Iterator for VerificationIter {
+    type Item = Result<VerificationStatus>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(self.stream.as_mut()?.next())
+    }
+}
+
+impl Drop for VerificationIter {
+    fn drop(&mut self) {
+        // Cancelling on drop is spawned onto the current runtime
+        let _guard = runtime().enter();
+        self.stream.take();
+    }
+}
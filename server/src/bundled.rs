@@ -1,27 +1,57 @@
-use std::path::{Path, PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{jar::ViperServerJar, paths};
 
 const VIPERSERVER_JAR_BYTES: &[u8] =
     include_bytes!("../viperserver/target/scala-2.13/viperserver.jar");
 
-#[derive(Debug)]
-pub struct ViperServerJar {
-    _dir: tempfile::TempDir,
-    path: PathBuf,
+/// The first part of the SHA-256 of the bundled jar, which names the directory
+/// it is extracted to.
+fn content_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        Sha256::digest(VIPERSERVER_JAR_BYTES)
+            .iter()
+            .take(8)
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    })
 }
 
 impl ViperServerJar {
+    /// Extract the bundled jar to `vipers/viperserver-<hash>` in the user's
+    /// cache directory, unless an earlier call, possibly from another process,
+    /// already did so.
+    ///
+    /// Falls back to [`ViperServerJar::create_temp`] if there is no cache
+    /// directory.
     pub fn create() -> std::io::Result<ViperServerJar> {
+        match paths::cache_dir() {
+            Some(cache) => Ok(ViperServerJar::cached(extract_to(&cache.join("vipers"))?)),
+            None => ViperServerJar::create_temp(),
+        }
+    }
+
+    /// Write the bundled jar to a temporary directory, which lives as long as
+    /// the returned value.
+    pub fn create_temp() -> std::io::Result<ViperServerJar> {
         let dir = tempfile::tempdir()?;
         let path = dir.path().join("viperserver.jar");
 
-        std::fs::write(&path, VIPERSERVER_JAR_BYTES)?;
+        fs::write(&path, VIPERSERVER_JAR_BYTES)?;
 
-        Ok(ViperServerJar { _dir: dir, path })
+        Ok(ViperServerJar::extracted(dir, path))
     }
 }
 
-impl AsRef<Path> for ViperServerJar {
-    fn as_ref(&self) -> &Path {
-        &self.path
-    }
+/// Extract the jar below `root`, and return its path.
+fn extract_to(root: &Path) -> std::io::Result<PathBuf> {
+    let dir = root.join(format!("viperserver-{}", content_hash()));
+    paths::extract_once(&dir, "viperserver.jar", VIPERSERVER_JAR_BYTES)
 }
@@ -1,158 +1,629 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
 
-use futures::{Stream, TryStreamExt};
-use reqwest::Url;
+use futures::{stream::BoxStream, Stream, TryStreamExt};
+use reqwest::{Method, Url};
 use serde::{Deserialize, Serialize};
 use tokio::io::AsyncBufReadExt;
 use tokio_stream::{wrappers::LinesStream, StreamExt};
-use tokio_util::io::StreamReader;
+use tokio_util::{io::StreamReader, sync::CancellationToken};
 
 use crate::{
+    args::{self, ArgsError, ToArgs},
     error::Result,
     error::ViperServerError,
+    http::{Http, HttpConfig},
     opts::{CarbonOpts, CarbonOptsBuilder, SiliconOpts, SiliconOptsBuilder},
-    server::ViperServer,
+    outcome::VerificationOutcome,
+    progress::Progress,
+    server::{ShutdownReport, ViperServer},
+    toolchain::{self, Tool},
+    verification::Backend,
 };
 
 pub use crate::verification::VerificationStatus;
 
 #[derive(Debug)]
 pub struct Client {
-    #[allow(unused)]
-    pub server: ViperServer,
+    /// The server this client keeps alive, if it was created from one. Clients
+    /// made with [`Client::connect`] do not own their server.
+    pub server: Option<ViperServer>,
     base: Url,
+    http: Http,
+    cancel_on_drop: bool,
+}
+
+/// What ViperServer answers requests about jobs that do not exist with.
+#[derive(Debug, Deserialize)]
+struct HandshakeResponse {
+    #[allow(unused)]
+    msg: String,
 }
 
 impl Client {
-    pub async fn new(mut server: ViperServer) -> Result<Client> {
-        match server.online_at().await {
-            Ok(base) => Ok(Self {
-                server,
-                base: Url::parse(&base)?,
-            }),
-            Err(err) => {
-                let mut stdout = "stdout:".to_string();
-                let mut stderr = "stderr:".to_string();
-
-                while let Ok(r) = server.stdout.try_recv() {
-                    stdout += "\n";
-                    stdout += &r;
-                }
-                while let Ok(r) = server.stderr.try_recv() {
-                    stdout += "\n";
-                    stderr += &r;
-                }
+    pub async fn new(server: ViperServer) -> Result<Client> {
+        Self::new_with(server, &HttpConfig::default()).await
+    }
 
-                Err(ViperServerError::ConnectToServerFailed {
-                    stdout,
-                    stderr,
-                    source: Box::new(err),
-                })
+    pub async fn new_with(mut server: ViperServer, http: &HttpConfig) -> Result<Client> {
+        let base = Url::parse(&server.online_at().await?)?;
+        Ok(Self {
+            server: Some(server),
+            base,
+            http: Http::new(http)?,
+            cancel_on_drop: false,
+        })
+    }
 
-                // Err(err).wrap_err(stdout).wrap_err(stderr)?
-            }
+    /// Connect to a ViperServer that is already running at `url`, such as
+    /// `http://localhost:7777`.
+    ///
+    /// The server is checked to actually be a ViperServer by asking it for
+    /// the statuses of a job that does not exist, which it answers with a
+    /// message. This does not change anything on the server.
+    pub async fn connect(url: impl AsRef<str>) -> Result<Client> {
+        Self::connect_with(url, &HttpConfig::default()).await
+    }
+
+    pub async fn connect_with(url: impl AsRef<str>, http: &HttpConfig) -> Result<Client> {
+        let mut base = Url::parse(url.as_ref())?;
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
         }
+        let client = Self {
+            server: None,
+            base,
+            http: Http::new(http)?,
+            cancel_on_drop: false,
+        };
+
+        let not_a_viper_server = |reason: String| ViperServerError::NotAViperServer {
+            url: client.base.to_string(),
+            reason,
+        };
+        let res = client
+            .http
+            .send(
+                Method::GET,
+                client.base.join(&format!("verify/{}", i32::MAX))?,
+                |b| b,
+            )
+            .await
+            .map_err(|err| not_a_viper_server(err.to_string()))?;
+        let body = res.text().await?;
+        serde_json::from_str::<HandshakeResponse>(&body)
+            .map_err(|err| not_a_viper_server(format!("unexpected response {body:?}: {err}")))?;
+
+        Ok(client)
+    }
+
+    /// The URL the server is reachable at.
+    pub fn base_url(&self) -> &Url {
+        &self.base
     }
 
-    async fn get_lines_streaming<Res: for<'a> Deserialize<'a>>(
+    pub(crate) fn http(&self) -> &Http {
+        &self.http
+    }
+
+    /// Shut down the server this client owns. See [`ViperServer::shutdown`].
+    ///
+    /// Returns `None` for clients that do not own their server, which is left
+    /// running.
+    pub async fn shutdown(mut self, timeout: Duration) -> Result<Option<ShutdownReport>> {
+        match &mut self.server {
+            Some(server) => Ok(Some(server.shutdown_with(&self.http, timeout).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Shut down the owned server gracefully once the client is dropped. See
+    /// [`ViperServer::shutdown_on_drop`].
+    pub fn shutdown_on_drop(&mut self, timeout: Option<Duration>) -> &mut Self {
+        if let Some(server) = &mut self.server {
+            server.shutdown_on_drop(timeout);
+        }
+        self
+    }
+
+    /// Have the streams from [`Client::check_on_verification`] cancel their
+    /// verification when they are dropped before it finishes.
+    pub fn cancel_on_drop(&mut self, cancel: bool) -> &mut Self {
+        self.cancel_on_drop = cancel;
+        self
+    }
+
+    /// Ask the server to stop the verification, using its discard endpoint.
+    /// Verifications that already finished are not affected.
+    pub async fn cancel(&self, v: &VerificationResponse) -> Result<()> {
+        discard(&self.http, &self.base, v.id).await
+    }
+
+    /// The lines of a streamed response, as they arrive.
+    pub(crate) async fn get_lines_streaming(
         &self,
         url: impl AsRef<str>,
-    ) -> Result<impl Stream<Item = Result<Res>>> {
-        let client = reqwest::Client::new();
-        let res = client.get(self.base.join(url.as_ref())?).send().await?;
+    ) -> Result<BoxStream<'static, Result<String>>> {
+        let res = self
+            .http
+            .send_streaming(Method::GET, self.base.join(url.as_ref())?)
+            .await?;
 
-        let reader = StreamReader::new(
-            res.bytes_stream()
-                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err)),
-        );
+        let reader = StreamReader::new(res.bytes_stream().map_err(std::io::Error::other));
 
         let lines = LinesStream::new(reader.lines());
-
-        Ok(lines.map(|l| {
-            let l = l?;
-            serde_json::from_str(&l).map_err(|source| ViperServerError::ParseJson {
-                json: l.to_string(),
-                source,
-            })
-        }))
+        Ok(match self.http.config.idle_timeout {
+            Some(timeout) => futures::StreamExt::boxed(idle_timeout(lines, timeout)),
+            None => futures::StreamExt::boxed(lines.map(|l| l.map_err(ViperServerError::from))),
+        })
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(url = %self.base, job_id = v.id))
+    )]
     pub async fn check_on_verification(
         &self,
         v: &VerificationResponse,
-    ) -> Result<impl Stream<Item = Result<VerificationStatus>>> {
-        self.get_lines_streaming(format!("verify/{}", v.id)).await
+    ) -> Result<VerificationStream> {
+        let lines = self.get_lines_streaming(format!("verify/{}", v.id)).await?;
+        Ok(self.verification_stream(v, lines))
     }
 
+    /// The statuses in `lines`, the streamed statuses of the verification `v`.
+    pub(crate) fn verification_stream(
+        &self,
+        v: &VerificationResponse,
+        lines: BoxStream<'static, Result<String>>,
+    ) -> VerificationStream {
+        let statuses = lines.map(|line| parse_line(line?));
+        VerificationStream::new(
+            self.http.clone(),
+            self.base.clone(),
+            v,
+            futures::StreamExt::boxed(statuses),
+            self.cancel_on_drop,
+        )
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(url = %self.base, request = body.ty(), backend = body.backend())
+        )
+    )]
     pub async fn post<R: ViperRequest>(&self, body: R) -> Result<R::Response> {
-        let client = reqwest::Client::new();
-        let res = client
-            .post(self.base.join(body.url())?)
-            .json(&serde_json::json!({
-                "type": body.ty(),
-                "arg": body,
-            }))
-            .send()
-            .await?;
+        send(&self.http, &self.base, &body).await
+    }
+
+    /// Send `request` and return the statuses of the verification it starts.
+    pub async fn verify_stream(&self, request: VerificationRequest) -> Result<VerificationStream> {
+        let response = self.post(request).await?;
+        self.check_on_verification(&response).await
+    }
+
+    /// Send `request` and wait for the verification to finish, collecting its
+    /// statuses into a [`VerificationOutcome`].
+    pub async fn verify(&self, request: VerificationRequest) -> Result<VerificationOutcome> {
+        let mut stream = self.verify_stream(request).await?;
+        let mut outcome = VerificationOutcome::default();
+        while let Some(status) = futures::StreamExt::next(&mut stream).await {
+            outcome.record(status?);
+        }
+        Ok(outcome)
+    }
 
-        let s = res.text().await?;
-        serde_json::from_str(&s).map_err(|source| ViperServerError::ParseJson {
-            json: s.to_string(),
-            source,
+    /// Flush the server's whole cache.
+    pub async fn flush_cache(&self) -> Result<MessageResponse> {
+        self.post(FlushCache).await
+    }
+
+    /// Flush the cached results of `backend` for `file`.
+    pub async fn flush_file_cache(
+        &self,
+        backend: Backend,
+        file: impl Into<PathBuf>,
+    ) -> Result<MessageResponse> {
+        self.post(FlushFileCache {
+            backend,
+            file: file.into(),
         })
+        .await
+    }
+
+    /// Ask the server to exit. Prefer [`Client::shutdown`] for servers this
+    /// client owns, which also waits for the process to exit.
+    pub async fn exit(&self) -> Result<MessageResponse> {
+        self.post(Exit).await
+    }
+}
+
+/// Parse a line of JSON, such as a streamed [`VerificationStatus`].
+pub(crate) fn parse_line<T: for<'a> Deserialize<'a>>(line: String) -> Result<T> {
+    serde_json::from_str(&line).map_err(|source| ViperServerError::ParseJson { json: line, source })
+}
+
+/// End `lines` with [`ViperServerError::StreamIdle`] if no line arrives within
+/// `timeout`.
+fn idle_timeout<S>(lines: S, timeout: Duration) -> impl Stream<Item = Result<String>>
+where
+    S: Stream<Item = std::io::Result<String>> + Unpin,
+{
+    futures::stream::unfold(Some(lines), move |lines| async move {
+        let mut lines = lines?;
+        match tokio::time::timeout(timeout, lines.next()).await {
+            Ok(Some(line)) => Some((line.map_err(ViperServerError::from), Some(lines))),
+            Ok(None) => None,
+            Err(_) => Some((Err(ViperServerError::StreamIdle { timeout }), None)),
+        }
+    })
+}
+
+pub(crate) async fn send<R: ViperRequest>(
+    http: &Http,
+    base: &Url,
+    request: &R,
+) -> Result<R::Response> {
+    let body = request.body()?;
+    let res = http
+        .send(
+            request.method(),
+            base.join(&request.url())?,
+            |builder| match &body {
+                Some(body) => builder.json(body),
+                None => builder,
+            },
+        )
+        .await?;
+
+    let s = res.text().await?;
+    let mut response = serde_json::from_str(&s).map_err(|source| ViperServerError::ParseJson {
+        json: s.to_string(),
+        source,
+    })?;
+    request.attach(&mut response);
+    Ok(response)
+}
+
+async fn discard(http: &Http, base: &Url, id: u64) -> Result<()> {
+    send(http, base, &Discard { id }).await?;
+    Ok(())
+}
+
+/// The statuses of a verification, as returned by
+/// [`Client::check_on_verification`].
+///
+/// When the verification is cancelled through [`VerificationStream::cancel`]
+/// or its [`cancellation_token`](VerificationStream::cancellation_token), the
+/// job is discarded on the server and the stream ends with
+/// [`VerificationStatus::Cancelled`].
+pub struct VerificationStream {
+    statuses: BoxStream<'static, Result<VerificationStatus>>,
+    token: CancellationToken,
+    finished: Arc<AtomicBool>,
+    cancel_on_drop: bool,
+    http: Http,
+    base: Url,
+    id: u64,
+    _staging: Option<Arc<tempfile::TempDir>>,
+}
+
+impl VerificationStream {
+    fn new(
+        http: Http,
+        base: Url,
+        v: &VerificationResponse,
+        statuses: BoxStream<'static, Result<VerificationStatus>>,
+        cancel_on_drop: bool,
+    ) -> VerificationStream {
+        struct State {
+            statuses: BoxStream<'static, Result<VerificationStatus>>,
+            token: CancellationToken,
+            finished: Arc<AtomicBool>,
+            http: Http,
+            base: Url,
+            id: u64,
+        }
+
+        let id = v.id;
+        let token = CancellationToken::new();
+        let finished = Arc::new(AtomicBool::new(false));
+        let state = State {
+            statuses,
+            token: token.clone(),
+            finished: finished.clone(),
+            http: http.clone(),
+            base: base.clone(),
+            id,
+        };
+        let statuses = futures::stream::unfold(state, |mut s| async move {
+            if s.finished.load(Ordering::SeqCst) {
+                return None;
+            }
+            tokio::select! {
+                biased;
+                _ = s.token.cancelled() => {
+                    s.finished.store(true, Ordering::SeqCst);
+                    let status = discard(&s.http, &s.base, s.id)
+                        .await
+                        .map(|()| VerificationStatus::Cancelled);
+                    Some((status, s))
+                }
+                status = s.statuses.next() => match status {
+                    Some(status) => Some((status, s)),
+                    None => {
+                        s.finished.store(true, Ordering::SeqCst);
+                        None
+                    }
+                },
+            }
+        });
+
+        VerificationStream {
+            statuses: futures::StreamExt::boxed(statuses),
+            token,
+            finished,
+            cancel_on_drop,
+            http,
+            base,
+            id,
+            _staging: v.staging.clone(),
+        }
+    }
+
+    /// The id of the verification job.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Cancel the verification. The stream yields
+    /// [`VerificationStatus::Cancelled`] next, and then ends.
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    /// A token that cancels the verification like
+    /// [`VerificationStream::cancel`], for use elsewhere.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// The progress of the verification, instead of its statuses.
+    pub fn progress(self) -> Progress<Self> {
+        Progress::new(self)
+    }
+
+    /// Cancel the verification if the stream is dropped before it ends.
+    /// Defaults to the setting of [`Client::cancel_on_drop`].
+    pub fn cancel_on_drop(&mut self, cancel: bool) -> &mut Self {
+        self.cancel_on_drop = cancel;
+        self
+    }
+}
+
+impl Stream for VerificationStream {
+    type Item = Result<VerificationStatus>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.statuses.as_mut().poll_next(cx)
+    }
+}
+
+impl Drop for VerificationStream {
+    fn drop(&mut self) {
+        if !self.cancel_on_drop || self.finished.load(Ordering::SeqCst) {
+            return;
+        }
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let http = self.http.clone();
+        let base = self.base.clone();
+        let id = self.id;
+        handle.spawn(async move {
+            let _ = discard(&http, &base, id).await;
+        });
+    }
+}
+
+impl std::fmt::Debug for VerificationStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VerificationStream")
+            .field("id", &self.id)
+            .field("cancel_on_drop", &self.cancel_on_drop)
+            .finish_non_exhaustive()
     }
 }
 
 pub trait ViperRequest: Serialize {
     type Response: for<'a> Deserialize<'a>;
-    fn url(&self) -> &'static str;
+    /// The endpoint, relative to the server's base URL.
+    fn url(&self) -> String;
     fn ty(&self) -> &'static str;
+    fn method(&self) -> Method {
+        Method::POST
+    }
+    /// The JSON body. By default the request is sent as the argument of a
+    /// `{"type": .., "arg": ..}` object.
+    fn body(&self) -> Result<Option<serde_json::Value>> {
+        let arg =
+            serde_json::to_value(self).map_err(|source| ViperServerError::SerializeRequest {
+                ty: self.ty(),
+                source,
+            })?;
+        Ok(Some(serde_json::json!({
+            "type": self.ty(),
+            "arg": arg,
+        })))
+    }
+    /// The backend the request is for, if any.
+    fn backend(&self) -> Option<&str> {
+        None
+    }
+    /// Attach to the response anything it needs to keep alive, such as staged
+    /// files.
+    fn attach(&self, _response: &mut Self::Response) {}
+    /// Whether the request starts a job on the server. Only such requests are
+    /// sent again by a [`Supervisor`](crate::Supervisor) when the server fails,
+    /// as others, like [`Exit`] or [`Discard`], act on the failed server.
+    fn starts_job(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum VerificationRequest {
     Carbon {
         opts: Box<CarbonOpts>,
-        file: PathBuf,
+        file: SourceFile,
     },
     Silicon {
         opts: Box<SiliconOpts>,
-        file: PathBuf,
+        file: SourceFile,
+    },
+    /// A backend the server loads by name, such as the class name of a custom
+    /// backend, with free-form arguments.
+    Custom {
+        backend: String,
+        args: Vec<String>,
+        file: SourceFile,
     },
 }
 
+/// The file a [`VerificationRequest`] verifies.
+///
+/// Files staged from source text by `verify_source` live in a temporary
+/// directory, which is kept alive by the request, its [`VerificationResponse`]
+/// and the [`VerificationStream`] of its statuses.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    path: PathBuf,
+    staging: Option<Arc<tempfile::TempDir>>,
+}
+
+impl SourceFile {
+    /// The name files staged by `verify_source` are given.
+    pub const DEFAULT_NAME: &'static str = "program.vpr";
+
+    /// Write `source` to a new temporary directory as `name`, which must be a
+    /// file name without any directories.
+    pub fn stage(name: &str, source: &str) -> Result<SourceFile> {
+        if Path::new(name).file_name() != Some(name.as_ref()) {
+            return Err(ViperServerError::InvalidSourceName {
+                name: name.to_string(),
+            });
+        }
+        let dir = tempfile::Builder::new().prefix("viperserver-").tempdir()?;
+        let path = dir.path().join(name);
+        std::fs::write(&path, source)?;
+        Ok(SourceFile {
+            path,
+            staging: Some(Arc::new(dir)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl From<PathBuf> for SourceFile {
+    fn from(path: PathBuf) -> Self {
+        SourceFile {
+            path,
+            staging: None,
+        }
+    }
+}
+
+impl From<&Path> for SourceFile {
+    fn from(path: &Path) -> Self {
+        path.to_path_buf().into()
+    }
+}
+
+impl AsRef<Path> for SourceFile {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
 impl Serialize for VerificationRequest {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let cmd = match self {
-            Self::Carbon { opts, file } => format!("carbon {opts} {file:?}"),
-            Self::Silicon { opts, file } => format!("silicon {opts} {file:?}"),
-        };
+        let cmd = self.command_line().map_err(serde::ser::Error::custom)?;
         serializer.serialize_str(&cmd)
     }
 }
 
 impl VerificationRequest {
+    /// The arguments of the request, starting with the backend and ending
+    /// with the file.
+    pub fn args(&self) -> Result<Vec<String>, ArgsError> {
+        let (backend, mut args, file) = match self {
+            Self::Carbon { opts, file } => ("carbon", opts.to_args(), file),
+            Self::Silicon { opts, file } => ("silicon", opts.to_args(), file),
+            Self::Custom {
+                backend,
+                args,
+                file,
+            } => (backend.as_str(), args.clone(), file),
+        };
+        args.insert(0, backend.to_string());
+        args.push(args::path_arg(file.path())?.to_string());
+        Ok(args)
+    }
+
+    /// The command line sent to the server, encoded with [`args::encode`].
+    pub fn command_line(&self) -> Result<String, ArgsError> {
+        args::encode(self.args()?)
+    }
+
     pub fn silicon() -> SiliconOptsBuilder {
         SiliconOptsBuilder::default()
     }
+    /// Verify `file` with the backend the server knows as `backend`, passing
+    /// it `args`.
+    pub fn custom<I>(backend: impl Into<String>, args: I, file: impl AsRef<Path>) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self::Custom {
+            backend: backend.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            file: file.as_ref().into(),
+        }
+    }
+    pub fn file(&self) -> &SourceFile {
+        match self {
+            Self::Carbon { file, .. } | Self::Silicon { file, .. } | Self::Custom { file, .. } => {
+                file
+            }
+        }
+    }
     pub fn carbon() -> CarbonOptsBuilder {
         CarbonOptsBuilder::default()
     }
 }
 
 impl SiliconOptsBuilder {
-    /// Try to detect `z3` from `PATH` using `which z3`
+    /// Find a supported `z3`, see [`toolchain::find`].
     pub fn detect_z3(&mut self) -> Result<&mut Self> {
-        let z3 = String::from_utf8(
-            std::process::Command::new("which")
-                .arg("z3")
-                .output()?
-                .stdout,
-        )?;
-        Ok(self.z3_exe(z3))
+        let z3 = toolchain::find(Tool::Z3)?;
+        Ok(self.z3_exe(z3.path.to_string_lossy()))
     }
     pub fn verify_file(&self, file: impl AsRef<Path>) -> Result<VerificationRequest> {
         Ok(VerificationRequest::Silicon {
@@ -160,17 +631,36 @@ impl SiliconOptsBuilder {
             file: file.as_ref().into(),
         })
     }
+    /// Verify `source`, staged as [`SourceFile::DEFAULT_NAME`].
+    pub fn verify_source(&self, source: &str) -> Result<VerificationRequest> {
+        self.verify_source_named(SourceFile::DEFAULT_NAME, source)
+    }
+    /// Verify `source`, staged as `name`, which is the file name errors are
+    /// reported in.
+    pub fn verify_source_named(&self, name: &str, source: &str) -> Result<VerificationRequest> {
+        Ok(VerificationRequest::Silicon {
+            opts: Box::new(self.build()?),
+            file: SourceFile::stage(name, source)?,
+        })
+    }
+    #[cfg(feature = "silvers")]
+    pub fn verify_program<E: std::fmt::Display>(
+        &self,
+        program: &silvers::program::Program<E>,
+    ) -> Result<VerificationRequest> {
+        self.verify_source(&program.to_string())
+    }
 }
 impl CarbonOptsBuilder {
-    /// Try to detect `z3` from `PATH` using `which z3`
+    /// Find a supported `z3`, see [`toolchain::find`].
     pub fn detect_z3(&mut self) -> Result<&mut Self> {
-        let z3 = String::from_utf8(
-            std::process::Command::new("which")
-                .arg("z3")
-                .output()?
-                .stdout,
-        )?;
-        Ok(self.z3_exe(z3))
+        let z3 = toolchain::find(Tool::Z3)?;
+        Ok(self.z3_exe(z3.path.to_string_lossy()))
+    }
+    /// Find a supported `boogie`, see [`toolchain::find`].
+    pub fn detect_boogie(&mut self) -> Result<&mut Self> {
+        let boogie = toolchain::find(Tool::Boogie)?;
+        Ok(self.boogie_exe(boogie.path.to_string_lossy()))
     }
     pub fn verify_file(&self, file: impl AsRef<Path>) -> Result<VerificationRequest> {
         Ok(VerificationRequest::Carbon {
@@ -178,12 +668,33 @@ impl CarbonOptsBuilder {
             file: file.as_ref().into(),
         })
     }
+    /// Verify `source`, staged as [`SourceFile::DEFAULT_NAME`].
+    pub fn verify_source(&self, source: &str) -> Result<VerificationRequest> {
+        self.verify_source_named(SourceFile::DEFAULT_NAME, source)
+    }
+    /// Verify `source`, staged as `name`, which is the file name errors are
+    /// reported in.
+    pub fn verify_source_named(&self, name: &str, source: &str) -> Result<VerificationRequest> {
+        Ok(VerificationRequest::Carbon {
+            opts: Box::new(self.build()?),
+            file: SourceFile::stage(name, source)?,
+        })
+    }
+    #[cfg(feature = "silvers")]
+    pub fn verify_program<E: std::fmt::Display>(
+        &self,
+        program: &silvers::program::Program<E>,
+    ) -> Result<VerificationRequest> {
+        self.verify_source(&program.to_string())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct VerificationResponse {
     pub ast_id: u64,
     pub id: u64,
+    #[serde(skip)]
+    staging: Option<Arc<tempfile::TempDir>>,
 }
 
 impl ViperRequest for VerificationRequest {
@@ -193,7 +704,133 @@ impl ViperRequest for VerificationRequest {
         "verify"
     }
 
-    fn url(&self) -> &'static str {
-        "verify"
+    fn url(&self) -> String {
+        "verify".to_string()
+    }
+
+    fn starts_job(&self) -> bool {
+        true
+    }
+
+    fn backend(&self) -> Option<&str> {
+        Some(match self {
+            Self::Carbon { .. } => "carbon",
+            Self::Silicon { .. } => "silicon",
+            Self::Custom { backend, .. } => backend,
+        })
+    }
+
+    fn attach(&self, response: &mut VerificationResponse) {
+        response.staging = self.file().staging.clone();
+    }
+}
+
+/// The answer to requests that only report back with a message.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageResponse {
+    pub msg: String,
+}
+
+/// Flush the server's whole cache.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FlushCache;
+
+impl ViperRequest for FlushCache {
+    type Response = MessageResponse;
+
+    fn ty(&self) -> &'static str {
+        "flush_cache"
+    }
+
+    fn url(&self) -> String {
+        "cache/flush".to_string()
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn body(&self) -> Result<Option<serde_json::Value>> {
+        Ok(None)
+    }
+}
+
+/// Flush the cached results of one backend for one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct FlushFileCache {
+    pub backend: Backend,
+    pub file: PathBuf,
+}
+
+impl ViperRequest for FlushFileCache {
+    type Response = MessageResponse;
+
+    fn ty(&self) -> &'static str {
+        "flush_file_cache"
+    }
+
+    fn url(&self) -> String {
+        "cache/flush".to_string()
+    }
+
+    fn body(&self) -> Result<Option<serde_json::Value>> {
+        Ok(Some(serde_json::json!({
+            "backend": self.backend.to_string(),
+            "file": args::path_arg(&self.file)?,
+        })))
+    }
+
+    fn backend(&self) -> Option<&str> {
+        Some(self.backend.as_str())
+    }
+}
+
+/// Ask the server to exit.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Exit;
+
+impl ViperRequest for Exit {
+    type Response = MessageResponse;
+
+    fn ty(&self) -> &'static str {
+        "exit"
+    }
+
+    fn url(&self) -> String {
+        "exit".to_string()
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn body(&self) -> Result<Option<serde_json::Value>> {
+        Ok(None)
+    }
+}
+
+/// Stop a verification job, see [`Client::cancel`].
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Discard {
+    pub id: u64,
+}
+
+impl ViperRequest for Discard {
+    type Response = MessageResponse;
+
+    fn ty(&self) -> &'static str {
+        "discard"
+    }
+
+    fn url(&self) -> String {
+        format!("discard/{}", self.id)
+    }
+
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn body(&self) -> Result<Option<serde_json::Value>> {
+        Ok(None)
     }
 }
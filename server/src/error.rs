@@ -4,6 +4,9 @@ pub type Result<T, E = ViperServerError> = std::result::Result<T, E>;
 
 #[derive(Debug, Error)]
 pub enum ViperServerError {
+    #[deprecated(
+        note = "startup failures are reported as `ServerExited`, `ServerStartupTimeout` or `ServerStartupFailed`, which carry the server output"
+    )]
     #[error("failed to connect to server")]
     ConnectToServerFailed {
         stdout: String,
@@ -16,15 +19,75 @@ pub enum ViperServerError {
     SiliconOpts(#[from] crate::opts::SiliconOptsBuilderError),
     #[error("building carbon from options")]
     CarbonOpts(#[from] crate::opts::CarbonOptsBuilderError),
+    #[error("building jvm config from options")]
+    JvmConfig(#[from] crate::jvm::JvmConfigBuilderError),
+    #[error("failed to run `{java} -version`")]
+    JavaVersion {
+        java: String,
+        source: std::io::Error,
+    },
+    #[error("could not determine the version of `{java}` from: {output:?}")]
+    UnrecognizedJavaVersion { java: String, output: String },
+    #[error("`{java}` is Java {found}, but ViperServer requires Java {minimum} or newer")]
+    UnsupportedJavaVersion {
+        java: String,
+        found: String,
+        minimum: u32,
+    },
+    #[error(
+        "could not find viperserver.jar, looked at: {}",
+        searched.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    JarNotFound { searched: Vec<std::path::PathBuf> },
+    #[error(transparent)]
+    Args(#[from] crate::args::ArgsError),
+    #[error("failed to serialize {ty} request")]
+    SerializeRequest {
+        ty: &'static str,
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Toolchain(#[from] crate::toolchain::ToolchainError),
     #[error("failed to spawn server using `{viper_server_jar}`")]
     SpawnServer {
         source: std::io::Error,
         viper_server_jar: String,
     },
+    #[error("server exited during startup with {status}")]
+    ServerExited {
+        status: std::process::ExitStatus,
+        code: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+    #[error("server did not come online within {timeout:?}")]
+    ServerStartupTimeout {
+        timeout: std::time::Duration,
+        stdout: String,
+        stderr: String,
+    },
+    #[error("server failed to start: {reason}")]
+    ServerStartupFailed { reason: String },
     #[error("server did not announce it's port")]
     ServerDidNotReportPort {
         source: tokio::sync::oneshot::error::RecvError,
     },
+    #[error("server failed and was restarted {restarts} times, giving up")]
+    RestartLimitReached { restarts: u32 },
+    #[error("building restart policy from options")]
+    RestartPolicy(#[from] crate::supervisor::RestartPolicyBuilderError),
+    #[error("`{name}` is not a plain file name")]
+    InvalidSourceName { name: String },
+    #[error("a server pool needs at least one server")]
+    EmptyPool,
+    #[error("`{url}` is not a ViperServer: {reason}")]
+    NotAViperServer { url: String, reason: String },
+    #[error("the language server answered with error {code}: {message}")]
+    Lsp { code: i64, message: String },
+    #[error("the connection to the language server was closed")]
+    LspClosed,
+    #[error("unexpected message from the language server: {reason}")]
+    LspProtocol { reason: String },
     #[error("failed to parse url")]
     InvalidUrl {
         #[from]
@@ -45,6 +108,16 @@ pub enum ViperServerError {
         json: String,
         source: serde_json::Error,
     },
+    #[error("the server sent no verification status for {timeout:?}")]
+    StreamIdle { timeout: std::time::Duration },
+    #[error("invalid fixture `{}` at line {line}: {reason}", path.display())]
+    InvalidFixture {
+        path: std::path::PathBuf,
+        line: usize,
+        reason: String,
+    },
+    #[error("no fixture was recorded for {command_line:?}")]
+    NoFixture { command_line: String },
     #[error("network error")]
     Network {
         #[from]
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use derive_builder::Builder;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+
+use crate::error::{Result, ViperServerError};
+
+/// Settings for the HTTP connection a [`Client`](crate::Client) keeps to its
+/// server.
+#[derive(Debug, Clone, Builder)]
+pub struct HttpConfig {
+    /// How long establishing a connection may take.
+    #[builder(setter(into, strip_option), default = "Some(Duration::from_secs(10))")]
+    pub connect_timeout: Option<Duration>,
+    /// How long a request may take, from sending it to receiving the whole
+    /// response. Does not apply to the streamed statuses of a verification,
+    /// see `idle_timeout`.
+    #[builder(setter(into, strip_option), default = "Some(Duration::from_secs(30))")]
+    pub request_timeout: Option<Duration>,
+    /// How long the server may go without sending a status while streaming
+    /// the statuses of a verification. A single member can take a long time
+    /// to verify, so keep this well above the backends' own timeouts.
+    #[builder(
+        setter(into, strip_option),
+        default = "Some(Duration::from_secs(15 * 60))"
+    )]
+    pub idle_timeout: Option<Duration>,
+    /// How many times idempotent (`GET`) requests are retried when the server
+    /// cannot be reached, times out or answers that it is unavailable.
+    #[builder(default = "2")]
+    pub retries: u32,
+    /// The delay before the first retry, which doubles with every retry.
+    #[builder(default = "Duration::from_millis(200)")]
+    pub retry_delay: Duration,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfigBuilder::default()
+            .build()
+            .expect("all fields have defaults")
+    }
+}
+
+/// A pooled HTTP client set up from a [`HttpConfig`]. Cheap to clone.
+#[derive(Debug, Clone)]
+pub(crate) struct Http {
+    client: reqwest::Client,
+    pub(crate) config: HttpConfig,
+}
+
+impl Http {
+    pub(crate) fn new(config: &HttpConfig) -> Result<Http> {
+        let mut client = reqwest::Client::builder();
+        if let Some(timeout) = config.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        Ok(Http {
+            client: client.build()?,
+            config: config.clone(),
+        })
+    }
+
+    /// Send the request built by `build`, retrying idempotent requests.
+    pub(crate) async fn send(
+        &self,
+        method: Method,
+        url: reqwest::Url,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+    ) -> Result<Response> {
+        self.send_inner(method, url, build, self.config.request_timeout)
+            .await
+    }
+
+    /// Like [`Http::send`], but without a timeout on the whole response, for
+    /// streamed responses.
+    pub(crate) async fn send_streaming(
+        &self,
+        method: Method,
+        url: reqwest::Url,
+    ) -> Result<Response> {
+        self.send_inner(method, url, |b| b, None).await
+    }
+
+    async fn send_inner(
+        &self,
+        method: Method,
+        url: reqwest::Url,
+        build: impl Fn(RequestBuilder) -> RequestBuilder,
+        timeout: Option<Duration>,
+    ) -> Result<Response> {
+        let retries = if method == Method::GET {
+            self.config.retries
+        } else {
+            0
+        };
+        let mut delay = self.config.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let mut request = build(self.client.request(method.clone(), url.clone()));
+            if let Some(timeout) = timeout {
+                request = request.timeout(timeout);
+            }
+            let result = request.send().await;
+            let retry = match &result {
+                Ok(res) => matches!(
+                    res.status(),
+                    StatusCode::SERVICE_UNAVAILABLE | StatusCode::TOO_MANY_REQUESTS
+                ),
+                Err(err) => err.is_connect() || err.is_timeout(),
+            };
+            if !retry || attempt >= retries {
+                return result.map_err(ViperServerError::from);
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
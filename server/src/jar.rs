@@ -0,0 +1,215 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use crate::{
+    error::{Result, ViperServerError},
+    jvm::JvmConfig,
+    paths,
+};
+
+/// The environment variable [`ViperServerJar::discover`] checks first.
+pub const VIPERSERVER_JAR_ENV: &str = "VIPERSERVER_JAR";
+
+/// Where a [`ViperServerJar`] was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JarSource {
+    /// Given explicitly with [`ViperServerJar::from_path`].
+    Path,
+    /// Named by the [`VIPERSERVER_JAR_ENV`] environment variable.
+    Env,
+    /// Extracted from the jar bundled with the `bundle-viperserver` feature.
+    Bundled,
+    /// Installed by the Viper IDE.
+    ViperIde,
+    /// Placed in the `viperserver` directory of the user's config directory.
+    ConfigDir,
+    /// Built in a ViperServer checkout.
+    Build,
+}
+
+/// A `viperserver.jar` to spawn servers from.
+#[derive(Debug)]
+pub struct ViperServerJar {
+    path: PathBuf,
+    source: JarSource,
+    /// Keeps an extracted jar alive
+    _dir: Option<tempfile::TempDir>,
+}
+
+/// The versions reported by `viperserver.jar --version`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JarVersions {
+    pub viperserver: Option<String>,
+    pub silicon: Option<String>,
+    pub carbon: Option<String>,
+    /// The full output, for versions reported in an unrecognized format.
+    pub output: String,
+}
+
+impl JarVersions {
+    /// Pick the versions out of lines such as `ViperServer 2.0.0 (...)`,
+    /// `Silicon: 1.1-SNAPSHOT` or `carbon version 1.0`.
+    pub fn parse(output: &str) -> JarVersions {
+        let mut versions = JarVersions {
+            output: output.to_string(),
+            ..Default::default()
+        };
+        for line in output.lines() {
+            let mut words = line
+                .split(|c: char| c.is_whitespace() || c == ':')
+                .filter(|w| !w.is_empty());
+            let Some(name) = words.next() else { continue };
+            let version = words
+                .find(|w| w.starts_with(|c: char| c.is_ascii_digit()))
+                .map(|v| v.trim_end_matches([',', ')']).to_string());
+            let slot = match name.to_ascii_lowercase().as_str() {
+                "viperserver" => &mut versions.viperserver,
+                "silicon" => &mut versions.silicon,
+                "carbon" => &mut versions.carbon,
+                _ => continue,
+            };
+            if slot.is_none() {
+                *slot = version;
+            }
+        }
+        versions
+    }
+}
+
+impl ViperServerJar {
+    pub fn from_path(path: impl Into<PathBuf>) -> ViperServerJar {
+        ViperServerJar {
+            path: path.into(),
+            source: JarSource::Path,
+            _dir: None,
+        }
+    }
+
+    /// Find a `viperserver.jar`, looking in order at
+    ///
+    /// 1. the path in the `VIPERSERVER_JAR` environment variable,
+    /// 2. the bundled jar, with the `bundle-viperserver` feature,
+    /// 3. the installations of the Viper IDE in VS Code,
+    /// 4. `viperserver/viperserver.jar` in the user's config directory, such as
+    ///    `~/.config` on Linux.
+    pub fn discover() -> Result<ViperServerJar> {
+        let mut searched = vec![];
+
+        if let Some(path) = std::env::var_os(VIPERSERVER_JAR_ENV).filter(|p| !p.is_empty()) {
+            let path = PathBuf::from(path);
+            if path.is_file() {
+                return Ok(ViperServerJar::found(path, JarSource::Env));
+            }
+            searched.push(path);
+        }
+
+        // If the bundled jar cannot be extracted, an installed one will do
+        #[cfg(feature = "bundle-viperserver")]
+        if let Ok(jar) = ViperServerJar::create() {
+            return Ok(jar);
+        }
+
+        for tools in paths::viper_tools_dirs() {
+            let path = tools.join("backends").join("viperserver.jar");
+            if path.is_file() {
+                return Ok(ViperServerJar::found(path, JarSource::ViperIde));
+            }
+            searched.push(path);
+        }
+
+        if let Some(config) = paths::config_dir() {
+            let path = config.join("viperserver").join("viperserver.jar");
+            if path.is_file() {
+                return Ok(ViperServerJar::found(path, JarSource::ConfigDir));
+            }
+            searched.push(path);
+        }
+
+        Err(ViperServerError::JarNotFound { searched })
+    }
+
+    /// The jar built by `sbt assembly` in a ViperServer checkout, which ends up
+    /// in `target/scala-<version>/viperserver.jar`.
+    pub fn from_build_dir(checkout: impl AsRef<Path>) -> Result<ViperServerJar> {
+        let target = checkout.as_ref().join("target");
+        let jar = std::fs::read_dir(&target)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("scala-"))
+            .map(|entry| entry.path().join("viperserver.jar"))
+            .find(|jar| jar.is_file());
+        match jar {
+            Some(jar) => Ok(ViperServerJar::found(jar, JarSource::Build)),
+            None => Err(ViperServerError::JarNotFound {
+                searched: vec![target.join("scala-*").join("viperserver.jar")],
+            }),
+        }
+    }
+
+    fn found(path: PathBuf, source: JarSource) -> ViperServerJar {
+        ViperServerJar {
+            path,
+            source,
+            _dir: None,
+        }
+    }
+
+    #[cfg(feature = "bundle-viperserver")]
+    pub(crate) fn cached(path: PathBuf) -> ViperServerJar {
+        ViperServerJar::found(path, JarSource::Bundled)
+    }
+
+    #[cfg(feature = "bundle-viperserver")]
+    pub(crate) fn extracted(dir: tempfile::TempDir, path: PathBuf) -> ViperServerJar {
+        ViperServerJar {
+            path,
+            source: JarSource::Bundled,
+            _dir: Some(dir),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn source(&self) -> JarSource {
+        self.source
+    }
+
+    /// Where to keep a class data sharing archive for this jar, for jars this
+    /// crate extracted itself. See [`JvmConfig::class_data_archive`].
+    pub fn class_data_archive(&self) -> Option<PathBuf> {
+        (self.source == JarSource::Bundled).then(|| self.path.with_extension("jsa"))
+    }
+
+    /// Run the jar with `--version` and collect the versions it reports.
+    pub async fn versions(&self, jvm: &JvmConfig) -> Result<JarVersions> {
+        // Without the class data archive, as a short run like this would
+        // leave behind an archive of barely any classes
+        let output = jvm
+            .command(&self.path)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .output()
+            .await?;
+        let mut text = String::from_utf8(output.stdout)?;
+        text.push_str(&String::from_utf8(output.stderr)?);
+        Ok(JarVersions::parse(&text))
+    }
+}
+
+impl AsRef<Path> for ViperServerJar {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Display for ViperServerJar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.path.display().fmt(f)
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use derive_builder::Builder;
+use tokio::process::Command;
+
+use crate::error::{Result, ViperServerError};
+
+/// The oldest Java release ViperServer runs on.
+pub const MINIMUM_JAVA_VERSION: u32 = 11;
+
+/// The oldest Java release that writes class data archives at exit, which
+/// [`JvmConfig::class_data_archive`] relies on.
+pub const CLASS_DATA_ARCHIVE_JAVA_VERSION: u32 = 13;
+
+/// Settings for the JVM that hosts ViperServer.
+///
+/// The defaults match what `ViperServer::spawn` has always used: `java` from
+/// `JAVA_HOME` or `PATH`, `-Xss128m` and `-Xmx4g`.
+#[derive(Debug, Clone, Builder)]
+pub struct JvmConfig {
+    /// Path to the `java` executable. Takes precedence over `java_home`.
+    #[builder(setter(into, strip_option), default)]
+    pub java: Option<PathBuf>,
+    /// The JDK to use. If neither this nor `java` is set, `JAVA_HOME` is
+    /// consulted before falling back to `java` from `PATH`.
+    #[builder(setter(into, strip_option), default)]
+    pub java_home: Option<PathBuf>,
+    /// Maximum heap size, passed as `-Xmx`.
+    #[builder(setter(into, strip_option), default = "Some(\"4g\".to_string())")]
+    pub max_heap: Option<String>,
+    /// Initial heap size, passed as `-Xms`.
+    #[builder(setter(into, strip_option), default)]
+    pub initial_heap: Option<String>,
+    /// Thread stack size, passed as `-Xss`.
+    #[builder(setter(into, strip_option), default = "Some(\"128m\".to_string())")]
+    pub stack_size: Option<String>,
+    /// System properties, passed as `-Dkey=value`.
+    #[builder(setter(custom), default)]
+    pub system_properties: Vec<(String, String)>,
+    /// Extra JVM flags such as `-XX:+UseSerialGC`, passed before `-jar`.
+    #[builder(setter(custom), default)]
+    pub jvm_args: Vec<String>,
+    /// Environment variables set for the JVM process.
+    #[builder(setter(custom), default)]
+    pub env: Vec<(String, String)>,
+    /// Working directory of the JVM process.
+    #[builder(setter(into, strip_option), default)]
+    pub current_dir: Option<PathBuf>,
+    /// A class data sharing (AppCDS) archive for the server's classes, which
+    /// cuts its startup time. An existing archive is used, and a missing one is
+    /// written by the first server when it exits. Java ignores archives that
+    /// do not match the jar.
+    ///
+    /// Only used on [`CLASS_DATA_ARCHIVE_JAVA_VERSION`] or newer. On older
+    /// releases, or if the version of `java` cannot be determined, servers
+    /// run without an archive.
+    ///
+    /// See [`ViperServerJar::class_data_archive`](crate::ViperServerJar::class_data_archive)
+    /// for a suitable location for the bundled jar.
+    #[builder(setter(into, strip_option), default)]
+    pub class_data_archive: Option<PathBuf>,
+    /// Check the Java version before spawning, and refuse to start on
+    /// anything older than [`MINIMUM_JAVA_VERSION`].
+    #[builder(default = "true")]
+    pub check_version: bool,
+}
+
+impl JvmConfigBuilder {
+    pub fn system_property(
+        &mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.system_properties
+            .get_or_insert_with(Vec::new)
+            .push((key.into(), value.into()));
+        self
+    }
+    pub fn jvm_arg(&mut self, arg: impl Into<String>) -> &mut Self {
+        self.jvm_args.get_or_insert_with(Vec::new).push(arg.into());
+        self
+    }
+    pub fn env(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.env
+            .get_or_insert_with(Vec::new)
+            .push((key.into(), value.into()));
+        self
+    }
+}
+
+impl Default for JvmConfig {
+    fn default() -> Self {
+        JvmConfigBuilder::default()
+            .build()
+            .expect("all fields have defaults")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaVersion {
+    /// The feature release, e.g. `17` for `17.0.2` and `8` for `1.8.0_292`.
+    pub major: u32,
+    /// The version string as reported by `java -version`.
+    pub full: String,
+}
+
+impl JavaVersion {
+    /// Parse the first line of `java -version`, such as
+    /// `openjdk version "17.0.2" 2022-01-18`.
+    pub fn parse(output: &str) -> Option<JavaVersion> {
+        let line = output.lines().find(|l| l.contains(" version "))?;
+        let (_, rest) = line.split_once('"')?;
+        let (full, _) = rest.split_once('"')?;
+        let mut parts = full.split(|c: char| !c.is_ascii_digit());
+        let major = match parts.next()?.parse().ok()? {
+            1 => parts.next()?.parse().ok()?,
+            major => major,
+        };
+        Some(JavaVersion {
+            major,
+            full: full.to_string(),
+        })
+    }
+}
+
+impl JvmConfig {
+    pub fn builder() -> JvmConfigBuilder {
+        JvmConfigBuilder::default()
+    }
+
+    /// The `java` executable this configuration launches.
+    pub fn java_executable(&self) -> PathBuf {
+        let exe = if cfg!(windows) { "java.exe" } else { "java" };
+        if let Some(java) = &self.java {
+            java.clone()
+        } else if let Some(home) = &self.java_home {
+            home.join("bin").join(exe)
+        } else if let Some(home) = std::env::var_os("JAVA_HOME").filter(|h| !h.is_empty()) {
+            PathBuf::from(home).join("bin").join(exe)
+        } else {
+            PathBuf::from(exe)
+        }
+    }
+
+    /// Run `java -version` and parse the result.
+    pub async fn java_version(&self) -> Result<JavaVersion> {
+        let java = self.java_executable();
+        let output = Command::new(&java)
+            .arg("-version")
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|source| ViperServerError::JavaVersion {
+                java: java.display().to_string(),
+                source,
+            })?;
+        // `java -version` reports on stderr, but some distributions use stdout
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        JavaVersion::parse(&stderr)
+            .or_else(|| JavaVersion::parse(&stdout))
+            .ok_or_else(|| ViperServerError::UnrecognizedJavaVersion {
+                java: java.display().to_string(),
+                output: format!("{stderr}{stdout}"),
+            })
+    }
+
+    /// Fail if the configured `java` is older than [`MINIMUM_JAVA_VERSION`].
+    pub async fn check_java_version(&self) -> Result<JavaVersion> {
+        let version = self.java_version().await?;
+        if version.major < MINIMUM_JAVA_VERSION {
+            return Err(ViperServerError::UnsupportedJavaVersion {
+                java: self.java_executable().display().to_string(),
+                found: version.full,
+                minimum: MINIMUM_JAVA_VERSION,
+            });
+        }
+        Ok(version)
+    }
+
+    /// The arguments passed to `java` before `-jar`, apart from those for the
+    /// [class data archive](JvmConfig::class_data_archive), which depend on
+    /// the Java version and are added when a server is spawned.
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(size) = &self.stack_size {
+            args.push(format!("-Xss{size}"));
+        }
+        if let Some(size) = &self.initial_heap {
+            args.push(format!("-Xms{size}"));
+        }
+        if let Some(size) = &self.max_heap {
+            args.push(format!("-Xmx{size}"));
+        }
+        for (key, value) in &self.system_properties {
+            args.push(format!("-D{key}={value}"));
+        }
+        args.extend(self.jvm_args.iter().cloned());
+        args
+    }
+
+    /// The command running `jar`, without the class data archive.
+    pub(crate) fn command(&self, jar: &Path) -> Command {
+        self.command_with(jar, None)
+    }
+
+    /// The command running a server from `jar` on `java`, with the class data
+    /// archive if `java` supports it.
+    pub(crate) fn server_command(
+        &self,
+        jar: &Path,
+        java: Option<&JavaVersion>,
+    ) -> (Command, Option<StagedArchive>) {
+        let archive = self
+            .class_data_archive
+            .as_ref()
+            .filter(|_| java.is_some_and(|v| v.major >= CLASS_DATA_ARCHIVE_JAVA_VERSION));
+        match archive {
+            None => (self.command(jar), None),
+            Some(archive) if archive.is_file() => {
+                let arg = format!("-XX:SharedArchiveFile={}", archive.display());
+                (self.command_with(jar, Some(arg)), None)
+            }
+            Some(archive) => {
+                let staged = StagedArchive::new(archive);
+                let arg = format!("-XX:ArchiveClassesAtExit={}", staged.staging.display());
+                (self.command_with(jar, Some(arg)), Some(staged))
+            }
+        }
+    }
+
+    fn command_with(&self, jar: &Path, archive_arg: Option<String>) -> Command {
+        let mut cmd = Command::new(self.java_executable());
+        cmd.args(self.args()).args(archive_arg).arg("-jar").arg(jar);
+        cmd.envs(self.env.iter().map(|(k, v)| (k, v)));
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+        cmd
+    }
+}
+
+/// A class data archive that a JVM writes when it exits.
+///
+/// Every JVM writes to a file of its own, which is renamed over the archive
+/// once the JVM has exited. JVMs that exit at the same time thus never write
+/// to the same file, and no JVM ever reads a half-written archive.
+#[derive(Debug)]
+pub(crate) struct StagedArchive {
+    pub(crate) staging: PathBuf,
+    archive: PathBuf,
+}
+
+impl StagedArchive {
+    pub(crate) fn new(archive: &Path) -> StagedArchive {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let mut name = archive.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        StagedArchive {
+            staging: archive.with_file_name(name),
+            archive: archive.to_path_buf(),
+        }
+    }
+
+    /// Move the archive into place if the JVM `exited` normally, which is
+    /// when it writes one, and discard it otherwise.
+    pub(crate) fn finish(self, exited: bool) -> io::Result<()> {
+        if exited && self.staging.is_file() {
+            return fs::rename(&self.staging, &self.archive);
+        }
+        match fs::remove_file(&self.staging) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
@@ -1,13 +1,40 @@
+pub mod args;
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 #[cfg(feature = "bundle-viperserver")]
 mod bundled;
 pub mod client;
 pub mod error;
+pub mod http;
+pub mod jar;
+pub mod jvm;
+pub mod logging;
+pub mod lsp;
 mod opts;
+pub mod outcome;
+pub mod output;
+mod paths;
+pub mod pool;
+mod process;
+pub mod progress;
+pub mod record;
 pub mod server;
+pub mod supervisor;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 #[cfg(test)]
 mod tests;
+pub mod toolchain;
 pub mod verification;
 
 pub use client::{Client, VerificationRequest, VerificationStatus};
 pub use error::ViperServerError;
-pub use server::ViperServer;
+pub use http::HttpConfig;
+pub use jar::ViperServerJar;
+pub use jvm::JvmConfig;
+pub use lsp::LspClient;
+pub use outcome::VerificationOutcome;
+pub use pool::ServerPool;
+pub use server::{ServerConfig, ViperServer};
+pub use supervisor::Supervisor;
@@ -0,0 +1,174 @@
+use crate::server::ViperServerLogLevel;
+
+/// A line of ViperServer output, split into the parts of its log format.
+///
+/// Lines that are not log messages, such as the output of the backends, are
+/// kept whole in `message`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogLine<'a> {
+    pub level: Option<ViperServerLogLevel>,
+    pub logger: Option<&'a str>,
+    /// The verification job the message is about, if it names one.
+    pub job_id: Option<u64>,
+    pub message: &'a str,
+}
+
+/// How many leading words may come before the level, such as the date, time
+/// and thread.
+const MAX_PREFIX_WORDS: usize = 4;
+
+impl<'a> LogLine<'a> {
+    pub fn parse(line: &'a str) -> LogLine<'a> {
+        let unstructured = LogLine {
+            level: None,
+            logger: None,
+            job_id: job_id(line),
+            message: line,
+        };
+
+        let mut rest = line.trim_start();
+        let mut level = None;
+        for _ in 0..=MAX_PREFIX_WORDS {
+            let (word, after) = split_word(rest);
+            if word.is_empty() {
+                break;
+            }
+            rest = after;
+            if let Some(l) = parse_level(word) {
+                level = Some(l);
+                break;
+            }
+        }
+        let Some(level) = level else {
+            return unstructured;
+        };
+
+        let (logger, after) = split_word(rest);
+        let logger = (!logger.is_empty()).then_some(logger);
+        let mut message = after;
+        // Drop the `[File.scala:12]` location and the `-` separator some
+        // patterns put before the message
+        if message.starts_with('[') {
+            if let Some((_, after)) = message.split_once(']') {
+                message = after.trim_start();
+            }
+        }
+        message = message.strip_prefix("- ").unwrap_or(message);
+
+        LogLine {
+            level: Some(level),
+            logger,
+            job_id: job_id(message),
+            message,
+        }
+    }
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    match s.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim_start()),
+        None => (s, ""),
+    }
+}
+
+fn parse_level(word: &str) -> Option<ViperServerLogLevel> {
+    Some(match word.trim_matches(|c| c == '[' || c == ']') {
+        "ALL" => ViperServerLogLevel::All,
+        "TRACE" => ViperServerLogLevel::Trace,
+        "DEBUG" => ViperServerLogLevel::Debug,
+        "INFO" => ViperServerLogLevel::Info,
+        "WARN" | "WARNING" => ViperServerLogLevel::Warn,
+        "ERROR" => ViperServerLogLevel::Error,
+        _ => return None,
+    })
+}
+
+/// Find the job id in messages mentioning `VerJobId(3)` or `job #3`.
+fn job_id(message: &str) -> Option<u64> {
+    ["VerJobId(", "job #", "Job #"].iter().find_map(|marker| {
+        let (_, rest) = message.split_once(marker)?;
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        rest[..end].parse().ok()
+    })
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) use emit::JobSpans;
+
+#[cfg(feature = "tracing")]
+mod emit {
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{Level, Span};
+
+    use super::LogLine;
+    use crate::server::ViperServerLogLevel;
+
+    /// The number of verification spans kept open per server.
+    const MAX_SPANS: usize = 256;
+
+    /// Re-emits server output as `tracing` events, inside a span per
+    /// verification job.
+    #[derive(Debug, Clone, Default)]
+    pub(crate) struct JobSpans {
+        spans: Arc<Mutex<VecDeque<(u64, Span)>>>,
+    }
+
+    impl JobSpans {
+        fn span(&self, job_id: u64) -> Span {
+            let mut spans = self.spans.lock().unwrap();
+            if let Some((_, span)) = spans.iter().find(|(id, _)| *id == job_id) {
+                return span.clone();
+            }
+            let span =
+                tracing::info_span!(target: "viperserver", parent: None, "verification", job_id);
+            if spans.len() == MAX_SPANS {
+                spans.pop_front();
+            }
+            spans.push_back((job_id, span.clone()));
+            span
+        }
+
+        /// Emit `line`, read from the server's `stream` (`stdout` or `stderr`).
+        pub(crate) fn emit(&self, stream: &'static str, line: &str) {
+            let line = LogLine::parse(line);
+            let level = match line.level {
+                Some(ViperServerLogLevel::All | ViperServerLogLevel::Trace) => Level::TRACE,
+                Some(ViperServerLogLevel::Debug) => Level::DEBUG,
+                Some(ViperServerLogLevel::Info) => Level::INFO,
+                Some(ViperServerLogLevel::Warn) => Level::WARN,
+                Some(ViperServerLogLevel::Error | ViperServerLogLevel::Off) => Level::ERROR,
+                None if stream == "stderr" => Level::WARN,
+                None => Level::INFO,
+            };
+            let span = line.job_id.map(|id| self.span(id));
+            let _entered = span.as_ref().map(Span::enter);
+
+            macro_rules! event {
+                ($level:expr) => {
+                    tracing::event!(
+                        target: "viperserver",
+                        $level,
+                        stream,
+                        logger = line.logger,
+                        "{}",
+                        line.message
+                    )
+                };
+            }
+            match level {
+                Level::TRACE => event!(Level::TRACE),
+                Level::DEBUG => event!(Level::DEBUG),
+                Level::INFO => event!(Level::INFO),
+                Level::WARN => event!(Level::WARN),
+                _ => event!(Level::ERROR),
+            }
+        }
+    }
+}
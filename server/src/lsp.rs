@@ -0,0 +1,563 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use reqwest::Url;
+use serde::{de, de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{tcp::OwnedWriteHalf, TcpStream},
+    sync::{broadcast, oneshot},
+    task::JoinHandle,
+};
+
+use crate::{
+    error::{Result, ViperServerError},
+    server::ViperServer,
+    verification::{DetailsError, OptionalPosition, Position},
+};
+
+type Pending = Arc<Mutex<PendingRequests>>;
+type Writer = Arc<tokio::sync::Mutex<OwnedWriteHalf>>;
+
+/// A client for a ViperServer started in LSP mode, speaking the protocol of
+/// the Viper IDE.
+#[derive(Debug)]
+pub struct LspClient {
+    /// The server this client keeps alive, if it was created from one.
+    pub server: Option<ViperServer>,
+    writer: Writer,
+    pending: Pending,
+    next_id: AtomicU64,
+    notifications: broadcast::Sender<LspNotification>,
+    reader: JoinHandle<()>,
+}
+
+/// The requests waiting for an answer.
+#[derive(Debug, Default)]
+struct PendingRequests {
+    senders: HashMap<u64, oneshot::Sender<Result<Value>>>,
+    /// Set once the connection is closed, after which no answers arrive
+    closed: bool,
+}
+
+/// The parameters of the `Verify` notification.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyParams {
+    /// The `file://` URI of the program to verify.
+    pub uri: String,
+    pub manually_triggered: bool,
+    pub workspace: String,
+    /// `silicon`, `carbon` or the name of a custom backend.
+    pub backend: String,
+    /// The arguments passed to the backend, excluding the file.
+    pub custom_args: String,
+    /// The program text, for servers that do not read it from disk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+/// `VerificationState` of the Viper IDE protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationState {
+    Stopped,
+    Starting,
+    VerificationRunning,
+    VerificationPrintingHelp,
+    VerificationReporting,
+    PostProcessing,
+    Ready,
+    Stopping,
+    Stage,
+    Unknown(i64),
+}
+
+impl From<i64> for VerificationState {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => Self::Stopped,
+            1 => Self::Starting,
+            2 => Self::VerificationRunning,
+            3 => Self::VerificationPrintingHelp,
+            4 => Self::VerificationReporting,
+            5 => Self::PostProcessing,
+            6 => Self::Ready,
+            7 => Self::Stopping,
+            8 => Self::Stage,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// `Success` of the Viper IDE protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationSuccess {
+    None,
+    Success,
+    ParsingFailed,
+    TypecheckingFailed,
+    VerificationFailed,
+    Aborted,
+    Error,
+    Timeout,
+    Stopped,
+    Unknown(i64),
+}
+
+impl From<i64> for VerificationSuccess {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => Self::None,
+            1 => Self::Success,
+            2 => Self::ParsingFailed,
+            3 => Self::TypecheckingFailed,
+            4 => Self::VerificationFailed,
+            5 => Self::Aborted,
+            6 => Self::Error,
+            7 => Self::Timeout,
+            8 => Self::Stopped,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+/// The `StateChange` notification. The protocol uses `-1` for absent numbers
+/// and `0`/`1` for booleans, which are mapped to `Option`s here.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateChange {
+    #[serde(deserialize_with = "from_i64")]
+    pub new_state: VerificationState,
+    #[serde(default, deserialize_with = "flag")]
+    pub manually_triggered: Option<bool>,
+    #[serde(default, deserialize_with = "flag")]
+    pub verification_completed: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub progress: Option<f64>,
+    #[serde(default, deserialize_with = "success")]
+    pub success: Option<VerificationSuccess>,
+    pub filename: Option<String>,
+    pub backend_name: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub time: Option<f64>,
+    #[serde(default, deserialize_with = "present")]
+    pub nof_errors: Option<f64>,
+    #[serde(default, deserialize_with = "flag")]
+    pub verification_needed: Option<bool>,
+    pub uri: Option<String>,
+    pub stage: Option<String>,
+    pub error: Option<String>,
+    #[serde(default)]
+    diagnostics: Option<Vec<Diagnostic>>,
+}
+
+impl StateChange {
+    /// The errors reported along with the state change.
+    pub fn errors(&self) -> Vec<DetailsError> {
+        let file = self
+            .uri
+            .as_deref()
+            .or(self.filename.as_deref())
+            .unwrap_or("");
+        self.diagnostics
+            .iter()
+            .flatten()
+            .map(|d| d.to_details_error(file))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LspNotification {
+    StateChange(StateChange),
+    /// `textDocument/publishDiagnostics`
+    Diagnostics {
+        uri: String,
+        errors: Vec<DetailsError>,
+    },
+    Log {
+        message: String,
+        level: Option<i64>,
+    },
+    Other {
+        method: String,
+        params: Value,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct LspPosition {
+    line: u64,
+    character: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Range {
+    start: LspPosition,
+    end: LspPosition,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Diagnostic {
+    range: Range,
+    #[serde(default)]
+    code: Option<Value>,
+    message: String,
+}
+
+impl Diagnostic {
+    /// Positions in LSP are zero-based, while ViperServer reports them
+    /// one-based as `line:column`.
+    fn to_details_error(&self, file: &str) -> DetailsError {
+        let pos = |p: &LspPosition| format!("{}:{}", p.line + 1, p.character + 1);
+        DetailsError {
+            cached: false,
+            position: OptionalPosition::Some(Position {
+                start: pos(&self.range.start),
+                end: pos(&self.range.end),
+                file: file.to_string(),
+            }),
+            tag: match &self.code {
+                Some(Value::String(code)) => code.as_str().into(),
+                Some(code) => code.to_string().into(),
+                None => "".into(),
+            },
+            text: self.message.clone(),
+        }
+    }
+}
+
+fn from_i64<'de, D: Deserializer<'de>, T: From<i64>>(d: D) -> Result<T, D::Error> {
+    Ok(i64::deserialize(d)?.into())
+}
+
+fn present<'de, D: Deserializer<'de>>(d: D) -> Result<Option<f64>, D::Error> {
+    Ok(Option::<f64>::deserialize(d)?.filter(|v| *v != -1.0))
+}
+
+fn flag<'de, D: Deserializer<'de>>(d: D) -> Result<Option<bool>, D::Error> {
+    match Option::<Value>::deserialize(d)? {
+        Some(Value::Bool(b)) => Ok(Some(b)),
+        Some(Value::Number(n)) => match n.as_i64() {
+            Some(0) => Ok(Some(false)),
+            Some(1) => Ok(Some(true)),
+            _ => Ok(None),
+        },
+        Some(Value::Null) | None => Ok(None),
+        Some(other) => Err(de::Error::custom(format!("expected a flag, found {other}"))),
+    }
+}
+
+fn success<'de, D: Deserializer<'de>>(d: D) -> Result<Option<VerificationSuccess>, D::Error> {
+    Ok(Option::<i64>::deserialize(d)?
+        .filter(|v| *v != -1)
+        .map(Into::into))
+}
+
+impl LspClient {
+    /// Connect to a server spawned with
+    /// `ViperServer::builder().spawn_lsp(..)` and initialize
+    /// the session.
+    pub async fn new(mut server: ViperServer) -> Result<LspClient> {
+        let url = Url::parse(&server.online_at().await?)?;
+        let mut client = Self::connect(url_address(&url)?).await?;
+        client.server = Some(server);
+        Ok(client)
+    }
+
+    /// Connect to an LSP-mode server that is already listening on `addr`,
+    /// such as `localhost:7778`, and initialize the session.
+    pub async fn connect(addr: impl AsRef<str>) -> Result<LspClient> {
+        let (read, write) = TcpStream::connect(addr.as_ref()).await?.into_split();
+
+        let writer: Writer = Arc::new(tokio::sync::Mutex::new(write));
+        let pending: Pending = Default::default();
+        let (notifications, _) = broadcast::channel(256);
+
+        let reader = tokio::spawn(read_messages(
+            BufReader::new(read),
+            writer.clone(),
+            pending.clone(),
+            notifications.clone(),
+        ));
+
+        let client = LspClient {
+            server: None,
+            writer,
+            pending,
+            next_id: AtomicU64::new(0),
+            notifications,
+            reader,
+        };
+
+        client
+            .request::<_, Value>(
+                "initialize",
+                json!({
+                    "processId": std::process::id(),
+                    "rootUri": null,
+                    "capabilities": {},
+                }),
+            )
+            .await?;
+        client.notify("initialized", json!({})).await?;
+
+        Ok(client)
+    }
+
+    /// Receive the notifications sent by the server from now on.
+    pub fn notifications(&self) -> broadcast::Receiver<LspNotification> {
+        self.notifications.subscribe()
+    }
+
+    /// Send a request and wait for its result.
+    pub async fn request<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<R> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.closed {
+                return Err(ViperServerError::LspClosed);
+            }
+            pending.senders.insert(id, tx);
+        }
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(err) = write_message(&mut *self.writer.lock().await, &message).await {
+            self.pending.lock().unwrap().senders.remove(&id);
+            return Err(err);
+        }
+
+        let result = rx.await.map_err(|_| ViperServerError::LspClosed)??;
+        serde_json::from_value(result.clone()).map_err(|source| ViperServerError::ParseJson {
+            json: result.to_string(),
+            source,
+        })
+    }
+
+    /// Send a notification.
+    pub async fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        write_message(&mut *self.writer.lock().await, &message).await
+    }
+
+    /// Open a document, which the server needs before it can verify it.
+    pub async fn did_open(&self, uri: &str, text: &str) -> Result<()> {
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "viper", "version": 0, "text": text },
+            }),
+        )
+        .await
+    }
+
+    pub async fn did_close(&self, uri: &str) -> Result<()> {
+        self.notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": uri } }),
+        )
+        .await
+    }
+
+    /// Start verifying a document. Progress and results arrive as
+    /// [`LspNotification::StateChange`]s.
+    pub async fn verify(&self, params: VerifyParams) -> Result<()> {
+        self.notify("Verify", params).await
+    }
+
+    /// Stop the verification of a document. Returns whether a verification
+    /// was stopped.
+    pub async fn stop_verification(&self, uri: &str) -> Result<bool> {
+        self.request("StopVerification", json!({ "uri": uri }))
+            .await
+    }
+
+    /// Flush the server's cache, either entirely or for one file and backend.
+    pub async fn flush_cache(&self, uri: Option<&str>, backend: Option<&str>) -> Result<()> {
+        self.request::<_, Value>("FlushCache", json!({ "uri": uri, "backend": backend }))
+            .await?;
+        Ok(())
+    }
+
+    /// End the session, after which the server may exit.
+    pub async fn shutdown(&self) -> Result<()> {
+        self.request::<_, Value>("shutdown", Value::Null).await?;
+        self.notify("exit", Value::Null).await
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// `host:port` of a `tcp://` URL announced by an LSP-mode server.
+fn url_address(url: &Url) -> Result<String> {
+    match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => Ok(format!("{host}:{port}")),
+        _ => Err(ViperServerError::LspProtocol {
+            reason: format!("server announced `{url}`, which has no host and port"),
+        }),
+    }
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    writer
+        .write_all(format!("Content-Length: {}\r\n\r\n{body}", body.len()).as_bytes())
+        .await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read one `Content-Length` framed message, or `None` at the end of the
+/// stream.
+async fn read_message<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let Some(len) = content_length else {
+        return Err(ViperServerError::LspProtocol {
+            reason: "message without Content-Length".to_string(),
+        });
+    };
+
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await?;
+    let body = String::from_utf8(body)?;
+    serde_json::from_str(&body)
+        .map(Some)
+        .map_err(|source| ViperServerError::ParseJson { json: body, source })
+}
+
+async fn read_messages<R: AsyncBufReadExt + Unpin>(
+    mut reader: R,
+    writer: Writer,
+    pending: Pending,
+    notifications: broadcast::Sender<LspNotification>,
+) {
+    while let Ok(Some(message)) = read_message(&mut reader).await {
+        let method = message.get("method").and_then(Value::as_str);
+        match (message.get("id"), method) {
+            // A request from the server
+            (Some(id), Some(method)) => {
+                let answer = answer(id, method);
+                let _ = write_message(&mut *writer.lock().await, &answer).await;
+            }
+            // A response to one of our requests
+            (Some(id), None) => {
+                let Some(tx) = id
+                    .as_u64()
+                    .and_then(|id| pending.lock().unwrap().senders.remove(&id))
+                else {
+                    continue;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(ViperServerError::Lsp {
+                        code: error
+                            .get("code")
+                            .and_then(Value::as_i64)
+                            .unwrap_or_default(),
+                        message: error
+                            .get("message")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                    }),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = tx.send(result);
+            }
+            (None, Some(method)) => {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                let _ = notifications.send(notification(method, params));
+            }
+            (None, None) => {}
+        }
+    }
+    // Fail everything that is still waiting for an answer, and any request
+    // made from now on
+    let mut pending = pending.lock().unwrap();
+    pending.closed = true;
+    pending.senders.clear();
+}
+
+/// The answer to a request the server sends to the client. The only one the
+/// server relies on is `GetViperFileEndings`.
+fn answer(id: &Value, method: &str) -> Value {
+    match method {
+        "GetViperFileEndings" => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": { "fileEndings": ["*.vpr", "*.sil"] },
+        }),
+        _ => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": format!("unsupported method `{method}`") },
+        }),
+    }
+}
+
+fn notification(method: &str, params: Value) -> LspNotification {
+    let parsed = match method {
+        "StateChange" => serde_json::from_value(params.clone())
+            .map(LspNotification::StateChange)
+            .ok(),
+        "textDocument/publishDiagnostics" => {
+            #[derive(Deserialize)]
+            struct Params {
+                uri: String,
+                diagnostics: Vec<Diagnostic>,
+            }
+            serde_json::from_value::<Params>(params.clone())
+                .map(|p| LspNotification::Diagnostics {
+                    errors: p
+                        .diagnostics
+                        .iter()
+                        .map(|d| d.to_details_error(&p.uri))
+                        .collect(),
+                    uri: p.uri,
+                })
+                .ok()
+        }
+        "Log" => Some(LspNotification::Log {
+            message: params
+                .get("data")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            level: params.get("logLevel").and_then(Value::as_i64),
+        }),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| LspNotification::Other {
+        method: method.to_string(),
+        params,
+    })
+}
@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use crate::verification::{Backend, DetailsError, Entity, ErrorKind, VerificationStatus};
+
+/// The result of a whole verification, collected from its
+/// [`VerificationStatus`]es by [`Client::verify`](crate::Client::verify).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationOutcome {
+    /// The backend that verified the program, once it reported a result.
+    pub verifier: Option<Backend>,
+    /// Whether the program parsed and typechecked. `None` if the server never
+    /// got that far.
+    pub ast_success: Option<bool>,
+    /// The parse and typecheck errors.
+    pub ast_errors: Vec<DetailsError>,
+    /// The errors in the arguments given to the backend.
+    pub invalid_args: Vec<DetailsError>,
+    /// The result of each verified member, in the order they were reported.
+    pub members: Vec<MemberOutcome>,
+    /// The result of the whole program, reported last.
+    pub overall: Option<OverallOutcome>,
+    pub exceptions: Vec<Exception>,
+    /// Whether the verification was cancelled before it finished.
+    pub cancelled: bool,
+    /// Every status received, in order.
+    pub messages: Vec<VerificationStatus>,
+}
+
+/// The result of verifying a single method, function or predicate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberOutcome {
+    pub entity: Entity,
+    pub success: bool,
+    pub errors: Vec<DetailsError>,
+    pub time: Duration,
+    /// Whether the result came from the server's cache.
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverallOutcome {
+    pub success: bool,
+    pub errors: Vec<DetailsError>,
+    pub time: Duration,
+}
+
+/// An exception the server hit while verifying.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Exception {
+    pub message: String,
+    pub stacktrace: Vec<String>,
+}
+
+impl VerificationOutcome {
+    /// Collect the outcome of `statuses`.
+    pub fn from_statuses(statuses: impl IntoIterator<Item = VerificationStatus>) -> Self {
+        let mut outcome = VerificationOutcome::default();
+        outcome.extend(statuses);
+        outcome
+    }
+
+    /// Take `status` into account.
+    pub fn record(&mut self, status: VerificationStatus) {
+        match &status {
+            VerificationStatus::AstConstructionResult { details, status } => {
+                self.ast_success = Some(status == "success");
+                self.ast_errors
+                    .extend(details.result.iter().flat_map(|r| r.errors.iter().cloned()));
+            }
+            VerificationStatus::InvalidArgsReport { errors, .. } => {
+                self.invalid_args.extend(errors.iter().cloned());
+            }
+            VerificationStatus::VerificationResult {
+                details,
+                kind,
+                status,
+                verifier,
+                ..
+            } => {
+                self.verifier = Some(verifier.clone());
+                let success = status == "success";
+                let errors = details
+                    .result
+                    .iter()
+                    .flat_map(|r| r.errors.iter().cloned())
+                    .collect();
+                let time = Duration::from_millis(details.time.max(0) as u64);
+                match (&details.entity, kind.as_str()) {
+                    (_, "overall") | (None, _) => {
+                        self.overall = Some(OverallOutcome {
+                            success,
+                            errors,
+                            time,
+                        })
+                    }
+                    (Some(entity), _) => self.members.push(MemberOutcome {
+                        entity: entity.clone(),
+                        success,
+                        errors,
+                        time,
+                        cached: details.cached.unwrap_or(false),
+                    }),
+                }
+            }
+            VerificationStatus::ExceptionReport {
+                message,
+                stacktrace,
+            } => self.exceptions.push(Exception {
+                message: message.clone(),
+                stacktrace: stacktrace.clone(),
+            }),
+            VerificationStatus::Cancelled => self.cancelled = true,
+            _ => {}
+        }
+        self.messages.push(status);
+    }
+
+    /// Whether the program verified: it parsed and typechecked, the backend
+    /// accepted its arguments, no exceptions were hit, the verification was
+    /// not cancelled, and no errors were reported.
+    pub fn is_success(&self) -> bool {
+        self.ast_success != Some(false)
+            && self.invalid_args.is_empty()
+            && self.exceptions.is_empty()
+            && !self.cancelled
+            && match &self.overall {
+                Some(overall) => overall.success,
+                None => self.ast_success == Some(true) && self.members.iter().all(|m| m.success),
+            }
+    }
+
+    /// All errors reported, without the duplicates of member errors in the
+    /// overall result.
+    pub fn errors(&self) -> impl Iterator<Item = &DetailsError> {
+        let overall = self
+            .overall
+            .iter()
+            .flat_map(|o| &o.errors)
+            .filter(|e| !self.members.iter().any(|m| m.errors.contains(e)));
+        self.ast_errors
+            .iter()
+            .chain(&self.invalid_args)
+            .chain(self.members.iter().flat_map(|m| &m.errors))
+            .chain(overall)
+    }
+
+    /// The errors of the given kind, see [`VerificationOutcome::errors`].
+    pub fn errors_of_kind<'a>(
+        &'a self,
+        kind: &'a ErrorKind,
+    ) -> impl Iterator<Item = &'a DetailsError> {
+        self.errors().filter(move |e| e.tag.kind == *kind)
+    }
+
+    /// The members of the given type, such as `"method"`, `"function"` or
+    /// `"predicate"`.
+    pub fn members_of_type<'a>(
+        &'a self,
+        entity_type: &'a str,
+    ) -> impl Iterator<Item = &'a MemberOutcome> {
+        self.members
+            .iter()
+            .filter(move |m| m.entity.entity_type == entity_type)
+    }
+
+    /// The result of the member named `name`.
+    pub fn member(&self, name: &str) -> Option<&MemberOutcome> {
+        self.members.iter().find(|m| m.entity.name == name)
+    }
+}
+
+impl Extend<VerificationStatus> for VerificationOutcome {
+    fn extend<T: IntoIterator<Item = VerificationStatus>>(&mut self, iter: T) {
+        for status in iter {
+            self.record(status);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{broadcast, watch};
+
+/// The output of one stream of the server process.
+///
+/// The most recent lines are kept in a bounded buffer, and new lines are
+/// broadcast to every subscriber. Nothing is queued for consumers that do not
+/// exist, so output that is never read does not pile up.
+#[derive(Debug, Clone)]
+pub struct OutputLog {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    capacity: usize,
+    state: Mutex<State>,
+    closed: watch::Sender<bool>,
+}
+
+#[derive(Debug)]
+struct State {
+    recent: VecDeque<String>,
+    /// Dropped once the stream ends, which lets subscribers see the end
+    lines: Option<broadcast::Sender<String>>,
+}
+
+impl OutputLog {
+    pub(crate) fn new(capacity: usize) -> OutputLog {
+        OutputLog {
+            shared: Arc::new(Shared {
+                capacity,
+                state: Mutex::new(State {
+                    recent: VecDeque::with_capacity(capacity),
+                    lines: Some(broadcast::channel(capacity.max(1)).0),
+                }),
+                closed: watch::channel(false).0,
+            }),
+        }
+    }
+
+    pub(crate) fn push(&self, line: String) {
+        let mut state = self.shared.state.lock().unwrap();
+        if self.shared.capacity > 0 {
+            if state.recent.len() == self.shared.capacity {
+                state.recent.pop_front();
+            }
+            state.recent.push_back(line.clone());
+        }
+        if let Some(lines) = &state.lines {
+            // Having no subscribers is fine
+            let _ = lines.send(line);
+        }
+    }
+
+    /// Mark the stream as ended.
+    pub(crate) fn close(&self) {
+        self.shared.state.lock().unwrap().lines = None;
+        self.shared.closed.send_replace(true);
+    }
+
+    /// The lines currently in the buffer, oldest first.
+    pub fn recent(&self) -> Vec<String> {
+        self.tail(usize::MAX)
+    }
+
+    /// The last `n` lines in the buffer, oldest first.
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let state = self.shared.state.lock().unwrap();
+        let skip = state.recent.len().saturating_sub(n);
+        state.recent.iter().skip(skip).cloned().collect()
+    }
+
+    /// Receive every line written from now on.
+    ///
+    /// A subscriber that falls more than the buffer capacity behind misses the
+    /// oldest lines and is told so with `RecvError::Lagged`. Once the stream
+    /// has ended and the remaining lines are received, `RecvError::Closed` is
+    /// returned.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.follow().1
+    }
+
+    /// The lines currently in the buffer, together with a subscription to the
+    /// ones after them. No line is missed or repeated in between.
+    pub fn follow(&self) -> (Vec<String>, broadcast::Receiver<String>) {
+        let state = self.shared.state.lock().unwrap();
+        let rx = match &state.lines {
+            Some(lines) => lines.subscribe(),
+            None => broadcast::channel(1).1,
+        };
+        (state.recent.iter().cloned().collect(), rx)
+    }
+
+    /// Whether the stream has ended, which happens when the process exits.
+    pub fn is_closed(&self) -> bool {
+        *self.shared.closed.borrow()
+    }
+
+    /// Wait for the stream to end.
+    pub async fn closed(&self) {
+        let mut closed = self.shared.closed.subscribe();
+        while !*closed.borrow_and_update() {
+            // The sender lives as long as `self`, so this does not fail
+            if closed.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Per-user directories, following the conventions of each platform.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use fs2::FileExt;
+
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    env::var_os(var)
+        .filter(|h| !h.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The directory applications keep their configuration in, such as
+/// `~/.config` on Linux and `%APPDATA%` on Windows.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("APPDATA")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        Some(home_dir()?.join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(home_dir()?.join(".config")))
+    }
+}
+
+/// The directory applications keep caches in, such as `~/.cache` on Linux and
+/// `%LOCALAPPDATA%` on Windows.
+#[cfg_attr(not(feature = "bundle-viperserver"), allow(dead_code))]
+pub(crate) fn cache_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("LOCALAPPDATA")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        Some(home_dir()?.join("Library/Caches"))
+    } else {
+        env::var_os("XDG_CACHE_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(home_dir()?.join(".cache")))
+    }
+}
+
+/// The user data directories of the VS Code flavours the Viper IDE runs in.
+fn vscode_user_dirs() -> Vec<PathBuf> {
+    let Some(config) = config_dir() else {
+        return vec![];
+    };
+    ["Code", "Code - Insiders", "VSCodium"]
+        .iter()
+        .map(|app| config.join(app).join("User"))
+        .collect()
+}
+
+/// The builds the Viper IDE installs, in the order they are preferred.
+const VIPER_IDE_BUILDS: &[&str] = &["Stable", "Nightly", "Local"];
+
+/// The `ViperTools` directories the Viper IDE installs into the global storage
+/// of its VS Code extension, holding ViperServer, Z3 and Boogie.
+pub(crate) fn viper_tools_dirs() -> Vec<PathBuf> {
+    vscode_user_dirs()
+        .into_iter()
+        .flat_map(|user| {
+            let storage = user.join("globalStorage").join("viper-admin.viper");
+            VIPER_IDE_BUILDS
+                .iter()
+                .map(move |build| storage.join(build).join("ViperTools"))
+        })
+        .collect()
+}
+
+/// Write `bytes` to `name` in `dir`, unless the file is already there, and
+/// return its path.
+///
+/// Processes doing this at the same time write the file only once, and a
+/// process that crashes halfway does not leave a truncated file behind.
+#[cfg_attr(not(feature = "bundle-viperserver"), allow(dead_code))]
+pub(crate) fn extract_once(dir: &Path, name: &str, bytes: &[u8]) -> io::Result<PathBuf> {
+    let path = dir.join(name);
+    fs::create_dir_all(dir)?;
+
+    let lock = File::create(dir.join(".lock"))?;
+    lock.lock_exclusive()?;
+
+    let complete = fs::metadata(&path)
+        .map(|m| m.len() == bytes.len() as u64)
+        .unwrap_or(false);
+    if !complete {
+        // Written next to the file and renamed into place
+        let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+        tmp.write_all(bytes)?;
+        tmp.persist(&path)?;
+    }
+
+    FileExt::unlock(&lock)?;
+    Ok(path)
+}
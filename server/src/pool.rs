@@ -0,0 +1,217 @@
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use futures::{Stream, StreamExt};
+use reqwest::Url;
+
+use crate::{
+    client::{Client, VerificationResponse, ViperRequest},
+    error::{Result, ViperServerError},
+    jvm::JvmConfig,
+    opts::ViperServerOptsBuilder,
+    server::{ServerConfig, ViperServer},
+    verification::VerificationStatus,
+};
+
+/// Several ViperServers, with verifications spread across them.
+///
+/// Each request is sent to the instance with the fewest verifications in
+/// progress. A verification counts as in progress until both its
+/// [`PoolResponse`] and the stream from
+/// [`ServerPool::check_on_verification`] are dropped. Other requests do not
+/// count towards the load of an instance.
+#[derive(Debug)]
+pub struct ServerPool {
+    instances: Vec<Instance>,
+}
+
+#[derive(Debug)]
+struct Instance {
+    client: Client,
+    load: Arc<Load>,
+}
+
+#[derive(Debug, Default)]
+struct Load {
+    active: AtomicUsize,
+    assigned: AtomicU64,
+    finished: AtomicU64,
+}
+
+/// Marks a verification as in progress for as long as it is alive.
+#[derive(Debug)]
+struct ActiveJob(Arc<Load>);
+
+impl ActiveJob {
+    fn start(load: &Arc<Load>) -> ActiveJob {
+        load.active.fetch_add(1, Ordering::SeqCst);
+        load.assigned.fetch_add(1, Ordering::SeqCst);
+        ActiveJob(load.clone())
+    }
+}
+
+impl Drop for ActiveJob {
+    fn drop(&mut self) {
+        self.0.active.fetch_sub(1, Ordering::SeqCst);
+        self.0.finished.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InstanceLoad {
+    pub index: usize,
+    pub url: Url,
+    /// Verifications currently in progress.
+    pub active: usize,
+    /// Verifications sent to this instance in total.
+    pub assigned: u64,
+    /// Verifications that are no longer in progress.
+    pub finished: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PoolResponse<T = VerificationResponse> {
+    /// The index of the instance that answered the request.
+    pub instance: usize,
+    pub response: T,
+    /// Only for requests that [start a job](ViperRequest::starts_job)
+    job: Option<Arc<ActiveJob>>,
+}
+
+impl ViperServerOptsBuilder {
+    /// Spawn `size` HTTP servers with these options. Any configured port is
+    /// ignored, so that each server picks its own. Fails if `size` is zero.
+    pub async fn spawn_pool(
+        &mut self,
+        size: usize,
+        jvm: &JvmConfig,
+        viper_server_jar: impl AsRef<Path>,
+    ) -> Result<ServerPool> {
+        ServerPool::spawn(size, self, jvm, viper_server_jar).await
+    }
+}
+
+impl ServerPool {
+    pub async fn spawn(
+        size: usize,
+        opts: &ViperServerOptsBuilder,
+        jvm: &JvmConfig,
+        viper_server_jar: impl AsRef<Path>,
+    ) -> Result<ServerPool> {
+        Self::spawn_configured(size, opts, jvm, &ServerConfig::default(), viper_server_jar).await
+    }
+
+    pub async fn spawn_configured(
+        size: usize,
+        opts: &ViperServerOptsBuilder,
+        jvm: &JvmConfig,
+        config: &ServerConfig,
+        viper_server_jar: impl AsRef<Path>,
+    ) -> Result<ServerPool> {
+        if size == 0 {
+            return Err(ViperServerError::EmptyPool);
+        }
+        let viper_server_jar = viper_server_jar.as_ref();
+        let mut opts = opts.build()?;
+        opts.port = None;
+        opts.server_mode = Some("HTTP".to_string());
+
+        let clients = futures::future::try_join_all((0..size).map(|_| async {
+            let server =
+                ViperServer::spawn_configured(viper_server_jar, jvm, config, opts.clone()).await?;
+            Client::new(server).await
+        }))
+        .await?;
+
+        Ok(ServerPool::from_clients(clients))
+    }
+
+    /// Build a pool from existing clients, which may or may not own their
+    /// servers.
+    pub fn from_clients(clients: impl IntoIterator<Item = Client>) -> ServerPool {
+        ServerPool {
+            instances: clients
+                .into_iter()
+                .map(|client| Instance {
+                    client,
+                    load: Default::default(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn clients(&self) -> impl Iterator<Item = &Client> {
+        self.instances.iter().map(|i| &i.client)
+    }
+
+    pub fn load(&self) -> Vec<InstanceLoad> {
+        self.instances
+            .iter()
+            .enumerate()
+            .map(|(index, i)| InstanceLoad {
+                index,
+                url: i.client.base_url().clone(),
+                active: i.load.active.load(Ordering::SeqCst),
+                assigned: i.load.assigned.load(Ordering::SeqCst),
+                finished: i.load.finished.load(Ordering::SeqCst),
+            })
+            .collect()
+    }
+
+    /// Send `request` to the least-loaded instance.
+    ///
+    /// A verification counts as in progress on that instance until the
+    /// [`PoolResponse`] is dropped. Fails if the pool is empty.
+    pub async fn post<R: ViperRequest>(&self, request: R) -> Result<PoolResponse<R::Response>> {
+        let (instance, chosen) = self
+            .instances
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, i)| {
+                (
+                    i.load.active.load(Ordering::SeqCst),
+                    i.load.assigned.load(Ordering::SeqCst),
+                )
+            })
+            .ok_or(ViperServerError::EmptyPool)?;
+        // Count the job before sending it, so that concurrent posts spread out
+        let job = request
+            .starts_job()
+            .then(|| Arc::new(ActiveJob::start(&chosen.load)));
+
+        let response = chosen.client.post(request).await?;
+        Ok(PoolResponse {
+            instance,
+            response,
+            job,
+        })
+    }
+
+    pub async fn check_on_verification(
+        &self,
+        v: &PoolResponse,
+    ) -> Result<impl Stream<Item = Result<VerificationStatus>>> {
+        let job = v.job.clone();
+        let stream = self.instances[v.instance]
+            .client
+            .check_on_verification(&v.response)
+            .await?;
+        Ok(stream.map(move |status| {
+            let _job = &job;
+            status
+        }))
+    }
+}
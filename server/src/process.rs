@@ -0,0 +1,122 @@
+use std::process::ExitStatus;
+
+use command_group::AsyncGroupChild;
+use tokio::sync::{mpsc, watch};
+
+#[derive(Debug, Clone, Copy)]
+enum Command {
+    Terminate,
+    Kill,
+}
+
+/// A handle to a child process owned by a background task.
+///
+/// The task reaps the child as soon as it exits and publishes its exit status.
+/// Once every handle is dropped the process group is killed, mirroring
+/// `kill_on_drop`.
+#[derive(Debug, Clone)]
+pub(crate) struct Process {
+    id: Option<u32>,
+    control: mpsc::UnboundedSender<Command>,
+    exit: watch::Receiver<Option<Result<ExitStatus, String>>>,
+}
+
+impl Process {
+    pub(crate) fn new(mut child: AsyncGroupChild) -> Process {
+        let id = child.id();
+        let (control, mut control_rx) = mpsc::unbounded_channel();
+        let (exit_tx, exit) = watch::channel(None);
+
+        tokio::spawn(async move {
+            // Wait on the group leader rather than the whole group: unlike the
+            // group wait, this can be cancelled without losing the exit status.
+            let status = loop {
+                tokio::select! {
+                    status = child.inner().wait() => break status,
+                    cmd = control_rx.recv() => match cmd {
+                        Some(Command::Terminate) => terminate(&mut child),
+                        Some(Command::Kill) => {
+                            let _ = child.kill();
+                        }
+                        None => {
+                            let _ = child.kill();
+                            break child.inner().wait().await;
+                        }
+                    },
+                }
+            };
+            // Take down whatever the server left behind in its group, such as
+            // prover processes
+            let _ = child.kill();
+            let _ = exit_tx.send(Some(status.map_err(|err| err.to_string())));
+        });
+
+        Process { id, control, exit }
+    }
+
+    pub(crate) fn id(&self) -> Option<u32> {
+        self.id
+    }
+
+    /// The exit status, if the process has exited.
+    pub(crate) fn try_status(&self) -> Option<std::io::Result<ExitStatus>> {
+        self.exit
+            .borrow()
+            .clone()
+            .map(|s| s.map_err(std::io::Error::other))
+    }
+
+    /// Wait for the process to exit.
+    pub(crate) async fn wait(&self) -> std::io::Result<ExitStatus> {
+        let mut exit = self.exit.clone();
+        loop {
+            if let Some(status) = self.try_status() {
+                return status;
+            }
+            if exit.changed().await.is_err() {
+                // The reaper task is gone, which only happens when the runtime
+                // is shutting down
+                return Err(std::io::Error::other("process reaper stopped"));
+            }
+        }
+    }
+
+    /// Call `f` with the exit status once the process has exited, without
+    /// keeping it alive. The status is `None` if it could not be determined.
+    pub(crate) fn on_exit(&self, f: impl FnOnce(Option<ExitStatus>) + Send + 'static) {
+        let mut exit = self.exit.clone();
+        tokio::spawn(async move {
+            let status = loop {
+                if let Some(status) = exit.borrow_and_update().clone() {
+                    break status.ok();
+                }
+                if exit.changed().await.is_err() {
+                    break None;
+                }
+            };
+            f(status);
+        });
+    }
+
+    /// Ask the process group to terminate. On Unix this sends `SIGTERM`, which
+    /// lets the JVM run its shutdown hooks. Elsewhere it kills outright.
+    pub(crate) fn terminate(&self) {
+        let _ = self.control.send(Command::Terminate);
+    }
+
+    /// Kill the process group.
+    pub(crate) fn kill(&self) {
+        let _ = self.control.send(Command::Kill);
+    }
+}
+
+#[cfg(unix)]
+fn terminate(child: &mut AsyncGroupChild) {
+    use command_group::{Signal, UnixChildExt};
+    let _ = child.signal(Signal::SIGTERM);
+}
+
+#[cfg(not(unix))]
+fn terminate(child: &mut AsyncGroupChild) {
+    let _ = child.kill();
+}
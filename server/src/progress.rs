@@ -0,0 +1,269 @@
+//! Progress of a verification, worked out from its statuses.
+//!
+//! ViperServer reports when members finish verifying, but not when they
+//! start, so progress is counted in finished members.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::Stream;
+
+use crate::{
+    error::Result,
+    verification::{Entity, VerificationStatus},
+};
+
+/// A step in the progress of a verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// The program was parsed.
+    Parsed,
+    /// The program was typechecked. Not sent if parsing or typechecking
+    /// failed.
+    Typechecked,
+    /// The number of members to verify became known, or was corrected by the
+    /// program outline.
+    Members { total: usize },
+    /// A member finished verifying.
+    MemberFinished {
+        /// How many members have finished, including this one.
+        index: usize,
+        /// The number of members to verify, if known yet.
+        total: Option<usize>,
+        entity: Entity,
+        success: bool,
+        /// Whether the result came from the server's cache.
+        cached: bool,
+        time: Duration,
+    },
+    /// The verification finished.
+    Completed {
+        success: bool,
+        /// The time the server reports for the whole verification.
+        time: Duration,
+        cached: usize,
+        fresh: usize,
+    },
+}
+
+/// Turns statuses into [`ProgressEvent`]s.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressTracker {
+    parsed: bool,
+    typechecked: bool,
+    total: Option<usize>,
+    /// Whether `total` was counted from the program outline
+    outlined: bool,
+    finished: usize,
+    cached: usize,
+    fresh: usize,
+    fresh_time: Duration,
+    completed: bool,
+}
+
+/// The kinds of members the backends verify one by one.
+const VERIFIED_MEMBERS: [&str; 3] = ["method", "function", "predicate"];
+
+impl ProgressTracker {
+    pub fn new() -> ProgressTracker {
+        ProgressTracker::default()
+    }
+
+    /// The events that `status` brings about.
+    pub fn update(&mut self, status: &VerificationStatus) -> Vec<ProgressEvent> {
+        let mut events = vec![];
+        match status {
+            VerificationStatus::WarningsDuringParsing(_) => self.parsed(&mut events),
+            VerificationStatus::WarningsDuringTypechecking(_) => {
+                self.parsed(&mut events);
+                self.typechecked(&mut events);
+            }
+            VerificationStatus::AstConstructionResult { status, .. } if status == "success" => {
+                self.parsed(&mut events);
+                self.typechecked(&mut events);
+            }
+            VerificationStatus::Statistics {
+                functions,
+                methods,
+                predicates,
+                ..
+            } => {
+                let total = (functions + methods + predicates).max(0) as usize;
+                self.members(total, false, &mut events);
+            }
+            VerificationStatus::ProgramOutline { members } => {
+                let total = members
+                    .iter()
+                    .filter(|m| VERIFIED_MEMBERS.contains(&m.member_type.as_str()))
+                    .count();
+                self.members(total, true, &mut events);
+            }
+            VerificationStatus::VerificationResult {
+                details,
+                kind,
+                status,
+                ..
+            } => {
+                let time = Duration::from_millis(details.time.max(0) as u64);
+                match (&details.entity, kind.as_str()) {
+                    (Some(entity), kind) if kind != "overall" => {
+                        let cached = details.cached.unwrap_or(false);
+                        self.finished += 1;
+                        if cached {
+                            self.cached += 1;
+                        } else {
+                            self.fresh += 1;
+                            self.fresh_time += time;
+                        }
+                        events.push(ProgressEvent::MemberFinished {
+                            index: self.finished,
+                            total: self.total,
+                            entity: entity.clone(),
+                            success: status == "success",
+                            cached,
+                            time,
+                        });
+                    }
+                    _ => self.completed(status == "success", time, &mut events),
+                }
+            }
+            VerificationStatus::VerificationTerminationMessage { .. }
+            | VerificationStatus::Cancelled => self.completed(false, Duration::ZERO, &mut events),
+            _ => {}
+        }
+        events
+    }
+
+    fn parsed(&mut self, events: &mut Vec<ProgressEvent>) {
+        if !std::mem::replace(&mut self.parsed, true) {
+            events.push(ProgressEvent::Parsed);
+        }
+    }
+
+    fn typechecked(&mut self, events: &mut Vec<ProgressEvent>) {
+        if !std::mem::replace(&mut self.typechecked, true) {
+            events.push(ProgressEvent::Typechecked);
+        }
+    }
+
+    fn members(&mut self, total: usize, outlined: bool, events: &mut Vec<ProgressEvent>) {
+        // The statistics count abstract members too, which never get a result,
+        // so the count from the outline takes precedence
+        if self.outlined || (self.total.is_some() && !outlined) {
+            return;
+        }
+        self.outlined = outlined;
+        if self.total != Some(total) {
+            self.total = Some(total);
+            events.push(ProgressEvent::Members { total });
+        }
+    }
+
+    fn completed(&mut self, success: bool, time: Duration, events: &mut Vec<ProgressEvent>) {
+        if !std::mem::replace(&mut self.completed, true) {
+            events.push(ProgressEvent::Completed {
+                success,
+                time,
+                cached: self.cached,
+                fresh: self.fresh,
+            });
+        }
+    }
+
+    /// The number of members that finished verifying.
+    pub fn finished(&self) -> usize {
+        self.finished
+    }
+
+    /// The number of members to verify, once known.
+    pub fn total(&self) -> Option<usize> {
+        self.total
+    }
+
+    /// The fraction of members that finished verifying, once known.
+    pub fn fraction(&self) -> Option<f64> {
+        match self.total? {
+            0 => Some(1.0),
+            total => Some((self.finished as f64 / total as f64).min(1.0)),
+        }
+    }
+
+    /// An estimate of the verification time left, from the average time of
+    /// the members verified so far. Cached members take no time.
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self.total?.saturating_sub(self.finished);
+        if self.fresh == 0 {
+            return (remaining == 0).then_some(Duration::ZERO);
+        }
+        Some(self.fresh_time / self.fresh as u32 * remaining as u32)
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.completed
+    }
+}
+
+/// The [`ProgressEvent`]s of a stream of statuses.
+pub struct Progress<S> {
+    statuses: S,
+    tracker: ProgressTracker,
+    pending: VecDeque<ProgressEvent>,
+}
+
+impl<S> Progress<S>
+where
+    S: Stream<Item = Result<VerificationStatus>> + Unpin,
+{
+    pub fn new(statuses: S) -> Progress<S> {
+        Progress {
+            statuses,
+            tracker: ProgressTracker::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// The progress so far, such as for an ETA.
+    pub fn tracker(&self) -> &ProgressTracker {
+        &self.tracker
+    }
+
+    pub fn into_inner(self) -> S {
+        self.statuses
+    }
+}
+
+impl<S> Stream for Progress<S>
+where
+    S: Stream<Item = Result<VerificationStatus>> + Unpin,
+{
+    type Item = Result<ProgressEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            if let Some(event) = this.pending.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            match Pin::new(&mut this.statuses).poll_next(cx) {
+                Poll::Ready(Some(Ok(status))) => {
+                    this.pending.extend(this.tracker.update(&status));
+                }
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Progress<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("tracker", &self.tracker)
+            .finish_non_exhaustive()
+    }
+}
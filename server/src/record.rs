@@ -0,0 +1,268 @@
+//! Recording the statuses the server sends for a verification, and replaying
+//! them without a server.
+//!
+//! A fixture is a file of JSON lines: a header with the request, followed by
+//! every line the server sent, as is, with the time it arrived.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, LineWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{stream::BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::{parse_line, Client, VerificationRequest, VerificationResponse, VerificationStream},
+    error::{Result, ViperServerError},
+    outcome::VerificationOutcome,
+    verification::VerificationStatus,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Entry {
+    Request {
+        command_line: String,
+        id: u64,
+        recorded_at_ms: u64,
+    },
+    Line {
+        elapsed_ms: u64,
+        line: String,
+    },
+}
+
+/// A recorded verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    /// The command line of the request, see
+    /// [`VerificationRequest::command_line`].
+    pub command_line: String,
+    /// The id of the verification job on the server that was recorded.
+    pub id: u64,
+    pub recorded_at: SystemTime,
+    pub lines: Vec<RecordedLine>,
+}
+
+/// A line sent by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedLine {
+    /// The time since the recording started.
+    pub elapsed: Duration,
+    pub line: String,
+}
+
+impl Fixture {
+    pub fn load(path: impl AsRef<Path>) -> Result<Fixture> {
+        let path = path.as_ref();
+        let invalid = |line: usize, reason: String| ViperServerError::InvalidFixture {
+            path: path.to_path_buf(),
+            line,
+            reason,
+        };
+
+        let mut fixture: Option<Fixture> = None;
+        for (i, text) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let text = text?;
+            if text.trim().is_empty() {
+                continue;
+            }
+            let entry =
+                serde_json::from_str(&text).map_err(|err| invalid(i + 1, err.to_string()))?;
+            match (entry, &mut fixture) {
+                (
+                    Entry::Request {
+                        command_line,
+                        id,
+                        recorded_at_ms,
+                    },
+                    None,
+                ) => {
+                    fixture = Some(Fixture {
+                        command_line,
+                        id,
+                        recorded_at: UNIX_EPOCH + Duration::from_millis(recorded_at_ms),
+                        lines: vec![],
+                    })
+                }
+                (Entry::Line { elapsed_ms, line }, Some(fixture)) => {
+                    fixture.lines.push(RecordedLine {
+                        elapsed: Duration::from_millis(elapsed_ms),
+                        line,
+                    })
+                }
+                (Entry::Request { .. }, Some(_)) => {
+                    return Err(invalid(i + 1, "a second request".to_string()))
+                }
+                (Entry::Line { .. }, None) => {
+                    return Err(invalid(i + 1, "a line before the request".to_string()))
+                }
+            }
+        }
+        fixture.ok_or_else(|| invalid(0, "the file is empty".to_string()))
+    }
+
+    /// The recorded statuses, immediately, or with the delays they were
+    /// recorded with if `realtime` is set.
+    pub fn replay(&self, realtime: bool) -> BoxStream<'static, Result<VerificationStatus>> {
+        let mut previous = Duration::ZERO;
+        let lines: Vec<_> = self
+            .lines
+            .iter()
+            .map(|recorded| {
+                let delay = recorded.elapsed.saturating_sub(previous);
+                previous = recorded.elapsed;
+                (delay, recorded.line.clone())
+            })
+            .collect();
+        futures::stream::iter(lines)
+            .then(move |(delay, line)| async move {
+                if realtime {
+                    tokio::time::sleep(delay).await;
+                }
+                parse_line(line)
+            })
+            .boxed()
+    }
+}
+
+/// Writes a fixture as the lines arrive.
+struct Recorder {
+    file: LineWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    fn create(path: &Path, command_line: String, id: u64) -> Result<Recorder> {
+        let mut recorder = Recorder {
+            file: LineWriter::new(File::create(path)?),
+            started: Instant::now(),
+        };
+        let recorded_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        recorder.write(&Entry::Request {
+            command_line,
+            id,
+            recorded_at_ms,
+        })?;
+        Ok(recorder)
+    }
+
+    fn record(&mut self, line: &str) -> Result<()> {
+        self.write(&Entry::Line {
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            line: line.to_string(),
+        })
+    }
+
+    fn write(&mut self, entry: &Entry) -> Result<()> {
+        let json = serde_json::to_string(entry).expect("fixture entries serialize");
+        writeln!(self.file, "{json}")?;
+        Ok(())
+    }
+}
+
+impl Client {
+    /// Like [`Client::check_on_verification`], but also records every line the
+    /// server sends for the verification of `request` to the fixture at
+    /// `fixture`.
+    pub async fn check_on_verification_recorded(
+        &self,
+        request: &VerificationRequest,
+        v: &VerificationResponse,
+        fixture: impl AsRef<Path>,
+    ) -> Result<VerificationStream> {
+        let mut recorder = Recorder::create(fixture.as_ref(), request.command_line()?, v.id)?;
+        let lines = self
+            .get_lines_streaming(format!("verify/{}", v.id))
+            .await?
+            .map(move |line| {
+                if let Ok(text) = &line {
+                    recorder.record(text)?;
+                }
+                line
+            });
+        Ok(self.verification_stream(v, lines.boxed()))
+    }
+
+    /// Send `request`, and record the statuses of the verification it starts
+    /// to the fixture at `fixture`.
+    pub async fn verify_recorded(
+        &self,
+        request: VerificationRequest,
+        fixture: impl AsRef<Path>,
+    ) -> Result<VerificationStream> {
+        let v = self.post(request.clone()).await?;
+        self.check_on_verification_recorded(&request, &v, fixture)
+            .await
+    }
+}
+
+/// Answers verifications from recorded fixtures instead of a server.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayClient {
+    fixtures: Vec<Fixture>,
+    realtime: bool,
+}
+
+impl ReplayClient {
+    pub fn new(fixtures: impl IntoIterator<Item = Fixture>) -> ReplayClient {
+        ReplayClient {
+            fixtures: fixtures.into_iter().collect(),
+            realtime: false,
+        }
+    }
+
+    /// Load the fixtures in `dir`, which end in `.ndjson`.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<ReplayClient> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<std::io::Result<_>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "ndjson"));
+        paths.sort();
+        let fixtures = paths
+            .iter()
+            .map(Fixture::load)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ReplayClient::new(fixtures))
+    }
+
+    /// Replay statuses with the delays they were recorded with.
+    pub fn realtime(&mut self, realtime: bool) -> &mut Self {
+        self.realtime = realtime;
+        self
+    }
+
+    pub fn fixtures(&self) -> &[Fixture] {
+        &self.fixtures
+    }
+
+    /// The recorded statuses of the fixture recorded for `request`, which is
+    /// the one with the same command line.
+    pub fn verify_stream(
+        &self,
+        request: &VerificationRequest,
+    ) -> Result<BoxStream<'static, Result<VerificationStatus>>> {
+        let command_line = request.command_line()?;
+        self.fixtures
+            .iter()
+            .find(|fixture| fixture.command_line == command_line)
+            .map(|fixture| fixture.replay(self.realtime))
+            .ok_or(ViperServerError::NoFixture { command_line })
+    }
+
+    /// See [`Client::verify`].
+    pub async fn verify(&self, request: &VerificationRequest) -> Result<VerificationOutcome> {
+        let mut statuses = self.verify_stream(request)?;
+        let mut outcome = VerificationOutcome::default();
+        while let Some(status) = statuses.next().await {
+            outcome.record(status?);
+        }
+        Ok(outcome)
+    }
+}
@@ -1,15 +1,22 @@
-use std::{path::Path, process::Stdio};
+use std::{
+    path::Path,
+    process::{ExitStatus, Stdio},
+    time::Duration,
+};
 
-use command_group::{AsyncCommandGroup, AsyncGroupChild};
+use command_group::AsyncCommandGroup;
+use derive_builder::Builder;
 use derive_more::Display;
 use serde::{Deserialize, Serialize};
-use tokio::{
-    io::{AsyncBufReadExt, BufReader},
-    process::Command,
-};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
 
+use crate::client::{self, Exit};
 use crate::error::{Result, ViperServerError};
+use crate::http::{Http, HttpConfig};
+use crate::jvm::JvmConfig;
 use crate::opts::{ViperServerOpts, ViperServerOptsBuilder};
+use crate::output::OutputLog;
+use crate::process::Process;
 
 #[derive(
     Debug, Display, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
@@ -57,27 +64,111 @@ pub enum ServerMode {
 enum OnlineAt {
     Waiting(tokio::sync::oneshot::Receiver<String>),
     Got(String),
-    Errored,
+    Errored(String),
 }
 
 #[derive(Debug)]
 pub struct ViperServer {
-    #[allow(unused)]
-    child: AsyncGroupChild,
+    process: Process,
     online_at: OnlineAt,
-    pub stdout: tokio::sync::mpsc::UnboundedReceiver<String>,
-    pub stderr: tokio::sync::mpsc::UnboundedReceiver<String>,
+    startup_timeout: Duration,
+    shutdown_on_drop: Option<Duration>,
+    maximum_active_jobs: usize,
+    pub stdout: OutputLog,
+    pub stderr: OutputLog,
+}
+
+/// Settings for running a [`ViperServer`], as opposed to the options passed to
+/// it and the JVM it runs on.
+#[derive(Debug, Clone, Builder)]
+pub struct ServerConfig {
+    /// How long the server may take to come online before
+    /// [`ViperServer::online_at`] gives up.
+    #[builder(default = "Duration::from_secs(60)")]
+    pub startup_timeout: Duration,
+    /// The number of recent stdout and stderr lines kept by the server's
+    /// [`OutputLog`]s.
+    #[builder(default = "1000")]
+    pub output_lines: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfigBuilder::default()
+            .build()
+            .expect("all fields have defaults")
+    }
+}
+
+impl ServerConfig {
+    pub fn builder() -> ServerConfigBuilder {
+        ServerConfigBuilder::default()
+    }
+}
+
+/// How many verifications ViperServer runs at once unless told otherwise with
+/// `--maximumActiveJobs`.
+pub const DEFAULT_MAXIMUM_ACTIVE_JOBS: usize = 3;
+
+/// The number of trailing output lines kept in a [`ShutdownReport`] and in
+/// startup errors.
+const REPORT_LINES: usize = 100;
+
+/// How long to wait for the output of an exited server to be read to the end.
+const OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+/// How the server process was brought down by [`ViperServer::shutdown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownMethod {
+    /// The process had already exited.
+    AlreadyExited,
+    /// The server exited after being asked to over HTTP.
+    Requested,
+    /// The process group exited after being sent `SIGTERM`.
+    Terminated,
+    /// The process group had to be killed.
+    Killed,
+}
+
+#[derive(Debug)]
+pub struct ShutdownReport {
+    pub status: ExitStatus,
+    pub method: ShutdownMethod,
+    /// The last lines written to stdout.
+    pub stdout: Vec<String>,
+    /// The last lines written to stderr.
+    pub stderr: Vec<String>,
 }
 
 impl ViperServerOptsBuilder {
-    async fn spawn(&self, viper_server_jar: impl AsRef<Path>) -> Result<ViperServer> {
-        ViperServer::spawn(viper_server_jar, self.build()?).await
+    async fn spawn(
+        &self,
+        jvm: &JvmConfig,
+        viper_server_jar: impl AsRef<Path>,
+    ) -> Result<ViperServer> {
+        ViperServer::spawn_with(viper_server_jar, jvm, self.build()?).await
     }
     pub async fn spawn_http(&mut self, viper_server_jar: impl AsRef<Path>) -> Result<ViperServer> {
-        self.server_mode("HTTP").spawn(viper_server_jar).await
+        self.spawn_http_with(&JvmConfig::default(), viper_server_jar)
+            .await
     }
     pub async fn spawn_lsp(&mut self, viper_server_jar: impl AsRef<Path>) -> Result<ViperServer> {
-        self.server_mode("LSP").spawn(viper_server_jar).await
+        self.spawn_lsp_with(&JvmConfig::default(), viper_server_jar)
+            .await
+    }
+    pub async fn spawn_http_with(
+        &mut self,
+        jvm: &JvmConfig,
+        viper_server_jar: impl AsRef<Path>,
+    ) -> Result<ViperServer> {
+        self.server_mode("HTTP").spawn(jvm, viper_server_jar).await
+    }
+    pub async fn spawn_lsp_with(
+        &mut self,
+        jvm: &JvmConfig,
+        viper_server_jar: impl AsRef<Path>,
+    ) -> Result<ViperServer> {
+        self.server_mode("LSP").spawn(jvm, viper_server_jar).await
     }
 }
 
@@ -87,17 +178,44 @@ impl ViperServer {
     }
 
     pub async fn spawn(viper_server_jar: impl AsRef<Path>, opts: ViperServerOpts) -> Result<Self> {
+        Self::spawn_with(viper_server_jar, &JvmConfig::default(), opts).await
+    }
+
+    pub async fn spawn_with(
+        viper_server_jar: impl AsRef<Path>,
+        jvm: &JvmConfig,
+        opts: ViperServerOpts,
+    ) -> Result<Self> {
+        Self::spawn_configured(viper_server_jar, jvm, &ServerConfig::default(), opts).await
+    }
+
+    pub async fn spawn_configured(
+        viper_server_jar: impl AsRef<Path>,
+        jvm: &JvmConfig,
+        config: &ServerConfig,
+        opts: ViperServerOpts,
+    ) -> Result<Self> {
         let viper_server_jar = viper_server_jar.as_ref();
-        let mut cmd = Command::new("java");
+        let java = if jvm.check_version {
+            Some(jvm.check_java_version().await?)
+        } else if jvm.class_data_archive.is_some() {
+            // Only needed to know whether the archive can be used
+            jvm.java_version().await.ok()
+        } else {
+            None
+        };
+
+        let (mut cmd, staged_archive) = jvm.server_command(viper_server_jar, java.as_ref());
         cmd.kill_on_drop(true);
-        cmd.arg("-Xss128m")
-            .arg("-Xmx4g")
-            .arg("-jar")
-            .arg(viper_server_jar);
 
         opts.apply(|a| {
             cmd.arg(a);
         });
+        let maximum_active_jobs = opts
+            .maximum_active_jobs
+            .as_deref()
+            .and_then(|jobs| jobs.trim().parse().ok())
+            .unwrap_or(DEFAULT_MAXIMUM_ACTIVE_JOBS);
 
         cmd.stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -110,65 +228,311 @@ impl ViperServer {
                 viper_server_jar: viper_server_jar.display().to_string(),
             })?;
 
-        let (stdout_tx, stdout_rx) = tokio::sync::mpsc::unbounded_channel();
-        let (stderr_tx, stderr_rx) = tokio::sync::mpsc::unbounded_channel();
-
         let inner_child = child.inner();
         let stdout = inner_child.stdout.take().unwrap();
         let stderr = inner_child.stderr.take().unwrap();
 
+        let stdout_log = OutputLog::new(config.output_lines);
+        let stderr_log = OutputLog::new(config.output_lines);
+
         let (online_at_tx, online_at_rx) = tokio::sync::oneshot::channel::<String>();
 
+        #[cfg(feature = "tracing")]
+        let job_spans = crate::logging::JobSpans::default();
+
+        let log = stdout_log.clone();
+        #[cfg(feature = "tracing")]
+        let spans = job_spans.clone();
         tokio::spawn(async move {
-            let mut lines = BufReader::new(stdout).lines();
+            let mut stdout = BufReader::new(stdout);
+            let mut buf = vec![];
 
             let mut online_at_tx = Some(online_at_tx);
 
-            while let Some(line) = lines.next_line().await.expect("failed to read line") {
+            while let Some(line) = next_line_lossy(&mut stdout, &mut buf).await {
                 if online_at_tx.is_some() {
-                    if let Some((_, url)) = line.split_once("ViperServer online at ") {
-                        online_at_tx.take().unwrap().send(url.to_string()).unwrap();
+                    if let Some(url) = announced_url(&line) {
+                        let _ = online_at_tx.take().unwrap().send(url);
                     }
                 }
 
-                stdout_tx
-                    .send(line)
-                    .expect("failed to send. did receiver close?");
+                #[cfg(feature = "tracing")]
+                spans.emit("stdout", &line);
+                log.push(line);
             }
+            log.close();
         });
 
+        let log = stderr_log.clone();
         tokio::spawn(async move {
-            let mut lines = BufReader::new(stderr).lines();
+            let mut stderr = BufReader::new(stderr);
+            let mut buf = vec![];
 
-            while let Some(line) = lines.next_line().await.expect("failed to read line") {
-                stderr_tx
-                    .send(line)
-                    .expect("failed to send. did receiver close?");
+            while let Some(line) = next_line_lossy(&mut stderr, &mut buf).await {
+                #[cfg(feature = "tracing")]
+                job_spans.emit("stderr", &line);
+                log.push(line);
             }
+            log.close();
         });
 
+        let process = Process::new(child);
+        if let Some(staged) = staged_archive {
+            process.on_exit(move |status| {
+                // A JVM that was killed had no chance to write the archive
+                let exited = status.is_some_and(|status| status.code().is_some());
+                let _ = staged.finish(exited);
+            });
+        }
+
         Ok(Self {
-            child,
+            process,
             online_at: OnlineAt::Waiting(online_at_rx),
-            stdout: stdout_rx,
-            stderr: stderr_rx,
+            startup_timeout: config.startup_timeout,
+            shutdown_on_drop: None,
+            maximum_active_jobs,
+            stdout: stdout_log,
+            stderr: stderr_log,
         })
     }
 
+    /// How long [`ViperServer::online_at`] waits for the server to announce
+    /// itself. Defaults to [`ServerConfig::startup_timeout`].
+    pub fn startup_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.startup_timeout = timeout;
+        self
+    }
+
+    /// How many verifications the server runs at once, as set with
+    /// `maximum_active_jobs`.
+    pub fn maximum_active_jobs(&self) -> usize {
+        self.maximum_active_jobs
+    }
+
+    /// Wait for the server to announce the URL it is reachable at.
+    ///
+    /// Fails if the server exits first, or does not come online within the
+    /// startup timeout. Once this has failed, later calls fail too.
     pub async fn online_at(&mut self) -> Result<String> {
         match &self.online_at {
             OnlineAt::Waiting(_) => {}
             OnlineAt::Got(url) => return Ok(url.clone()),
-            OnlineAt::Errored => todo!(),
+            OnlineAt::Errored(reason) => {
+                return Err(ViperServerError::ServerStartupFailed {
+                    reason: reason.clone(),
+                })
+            }
         }
-        if let OnlineAt::Waiting(rx) = std::mem::replace(&mut self.online_at, OnlineAt::Errored) {
-            let url = rx
-                .await
-                .map_err(|source| ViperServerError::ServerDidNotReportPort { source })?;
-            self.online_at = OnlineAt::Got(url.clone());
-            Ok(url)
-        } else {
+        let OnlineAt::Waiting(rx) = std::mem::replace(
+            &mut self.online_at,
+            OnlineAt::Errored("startup was interrupted".to_string()),
+        ) else {
             unreachable!()
+        };
+
+        let res = tokio::select! {
+            biased;
+            url = rx => match url {
+                Ok(url) => Ok(url),
+                // stdout closed without the server announcing itself, which
+                // usually means that it is exiting
+                Err(source) => match tokio::time::timeout(OUTPUT_GRACE, self.process.wait()).await {
+                    Ok(status) => Err(self.exited_during_startup(status).await),
+                    Err(_) => Err(ViperServerError::ServerDidNotReportPort { source }),
+                },
+            },
+            status = self.process.wait() => Err(self.exited_during_startup(status).await),
+            _ = tokio::time::sleep(self.startup_timeout) => {
+                // Do not leave behind a JVM that nobody is waiting for
+                self.process.kill();
+                Err(ViperServerError::ServerStartupTimeout {
+                    timeout: self.startup_timeout,
+                    stdout: self.stdout.tail(REPORT_LINES).join("\n"),
+                    stderr: self.stderr.tail(REPORT_LINES).join("\n"),
+                })
+            }
+        };
+
+        match res {
+            Ok(url) => {
+                self.online_at = OnlineAt::Got(url.clone());
+                Ok(url)
+            }
+            Err(err) => {
+                self.online_at = OnlineAt::Errored(err.to_string());
+                Err(err)
+            }
+        }
+    }
+
+    async fn exited_during_startup(
+        &mut self,
+        status: std::io::Result<ExitStatus>,
+    ) -> ViperServerError {
+        let status = match status {
+            Ok(status) => status,
+            Err(err) => {
+                return ViperServerError::ServerStartupFailed {
+                    reason: format!("could not wait for the server process: {err}"),
+                }
+            }
+        };
+        ViperServerError::ServerExited {
+            code: status.code(),
+            status,
+            stdout: report_lines(&self.stdout, OUTPUT_GRACE).await.join("\n"),
+            stderr: report_lines(&self.stderr, OUTPUT_GRACE).await.join("\n"),
         }
     }
+
+    /// The OS process id of the server.
+    pub fn id(&self) -> Option<u32> {
+        self.process.id()
+    }
+
+    /// The exit status of the server, if it has exited.
+    pub fn try_wait(&self) -> Result<Option<ExitStatus>> {
+        Ok(self.process.try_status().transpose()?)
+    }
+
+    /// Wait for the server to exit.
+    pub async fn wait(&self) -> Result<ExitStatus> {
+        Ok(self.process.wait().await?)
+    }
+
+    /// Kill the server's process group without waiting for it to exit.
+    pub fn kill(&self) {
+        self.process.kill();
+    }
+
+    /// Have the server shut down gracefully when it is dropped, instead of
+    /// being killed. See [`ViperServer::shutdown`] for what happens.
+    ///
+    /// This needs a Tokio runtime to be running at the time of the drop. Without
+    /// one the server is killed as usual.
+    pub fn shutdown_on_drop(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.shutdown_on_drop = timeout;
+        self
+    }
+
+    /// Shut the server down, giving it the chance to write its cache and log
+    /// files.
+    ///
+    /// The server is first asked to exit over HTTP. If it is still running
+    /// after `timeout`, its process group is sent `SIGTERM`, and if that does
+    /// not bring it down within another `timeout`, it is killed.
+    pub async fn shutdown(&mut self, timeout: Duration) -> Result<ShutdownReport> {
+        self.shutdown_with(&Http::new(&HttpConfig::default())?, timeout)
+            .await
+    }
+
+    /// Like [`ViperServer::shutdown`], asking the server to exit through
+    /// `http`.
+    pub(crate) async fn shutdown_with(
+        &mut self,
+        http: &Http,
+        timeout: Duration,
+    ) -> Result<ShutdownReport> {
+        let (status, method) = shutdown(&self.process, http, self.base_url(), timeout).await?;
+
+        Ok(ShutdownReport {
+            status,
+            method,
+            stdout: report_lines(&self.stdout, timeout).await,
+            stderr: report_lines(&self.stderr, timeout).await,
+        })
+    }
+
+    /// The URL the server announced, if it came online.
+    fn base_url(&self) -> Option<url::Url> {
+        match &self.online_at {
+            OnlineAt::Got(url) => url::Url::parse(url).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for ViperServer {
+    fn drop(&mut self) {
+        let Some(timeout) = self.shutdown_on_drop else {
+            return;
+        };
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let Ok(http) = Http::new(&HttpConfig::default()) else {
+            return;
+        };
+        let base = self.base_url();
+        // The spawned task keeps the process alive until it is done with it
+        let process = self.process.clone();
+        handle.spawn(async move {
+            let _ = shutdown(&process, &http, base, timeout).await;
+        });
+    }
+}
+
+/// Find the address the server announces once it is online. In HTTP mode this
+/// is the URL, while in LSP mode only the port is printed, which is turned into
+/// a `tcp://localhost:<port>` URL.
+fn announced_url(line: &str) -> Option<String> {
+    if let Some((_, url)) = line.split_once("ViperServer online at ") {
+        return Some(url.to_string());
+    }
+    let (_, rest) = line.split_once("<ViperServerPort:")?;
+    let (port, _) = rest.split_once('>')?;
+    Some(format!("tcp://localhost:{port}"))
+}
+
+/// Read the next line of output, replacing anything that is not UTF-8, such
+/// as messages in the encoding of the system locale. `None` at the end of the
+/// output.
+async fn next_line_lossy<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+) -> Option<String> {
+    buf.clear();
+    match reader.read_until(b'\n', buf).await {
+        Ok(0) | Err(_) => None,
+        Ok(_) => {
+            let line = buf.strip_suffix(b"\n").unwrap_or(buf);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            Some(String::from_utf8_lossy(line).into_owned())
+        }
+    }
+}
+
+async fn shutdown(
+    process: &Process,
+    http: &Http,
+    base: Option<url::Url>,
+    timeout: Duration,
+) -> Result<(ExitStatus, ShutdownMethod)> {
+    if let Some(status) = process.try_status() {
+        return Ok((status?, ShutdownMethod::AlreadyExited));
+    }
+
+    if let Some(base) = base {
+        let exit = tokio::time::timeout(timeout, client::send(http, &base, &Exit));
+        if let Ok(Ok(_)) = exit.await {
+            if let Ok(status) = tokio::time::timeout(timeout, process.wait()).await {
+                return Ok((status?, ShutdownMethod::Requested));
+            }
+        }
+    }
+
+    process.terminate();
+    if let Ok(status) = tokio::time::timeout(timeout, process.wait()).await {
+        return Ok((status?, ShutdownMethod::Terminated));
+    }
+
+    process.kill();
+    Ok((process.wait().await?, ShutdownMethod::Killed))
+}
+
+/// Wait up to `timeout` for an exiting server's output to be read to the end,
+/// and return its last [`REPORT_LINES`].
+async fn report_lines(log: &OutputLog, timeout: Duration) -> Vec<String> {
+    let _ = tokio::time::timeout(timeout, log.closed()).await;
+    log.tail(REPORT_LINES)
 }
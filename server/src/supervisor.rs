@@ -0,0 +1,431 @@
+use std::{
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Arc, RwLock, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use derive_builder::Builder;
+use futures::{stream::BoxStream, Stream, StreamExt};
+use reqwest::{Method, Url};
+use tokio::sync::{broadcast, watch};
+
+use crate::{
+    client::{Client, VerificationRequest, ViperRequest},
+    error::{Result, ViperServerError},
+    jvm::JvmConfig,
+    opts::ViperServerOpts,
+    server::{ServerConfig, ViperServer},
+    verification::VerificationStatus,
+};
+
+/// When and how often a [`Supervisor`] restarts its server.
+#[derive(Debug, Clone, Builder)]
+pub struct RestartPolicy {
+    /// The number of restarts after which the supervisor gives up.
+    #[builder(default = "5")]
+    pub max_restarts: u32,
+    /// How long a server has to stay healthy for the count of restarts to
+    /// start over, so that failures spread over a long run do not add up to
+    /// `max_restarts`.
+    #[builder(default = "Duration::from_secs(10 * 60)")]
+    pub reset_after: Duration,
+    /// The delay before the first restart. It doubles with every restart.
+    #[builder(default = "Duration::from_millis(500)")]
+    pub initial_backoff: Duration,
+    /// The longest delay between restarts.
+    #[builder(default = "Duration::from_secs(30)")]
+    pub max_backoff: Duration,
+    /// How often the server is checked for responsiveness.
+    #[builder(default = "Duration::from_secs(5)")]
+    pub health_check_interval: Duration,
+    /// How long a health check may take before it counts as failed.
+    #[builder(default = "Duration::from_secs(5)")]
+    pub health_check_timeout: Duration,
+    /// The number of consecutive failed health checks after which the server
+    /// is considered unresponsive.
+    #[builder(default = "3")]
+    pub unresponsive_after: u32,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicyBuilder::default()
+            .build()
+            .expect("all fields have defaults")
+    }
+}
+
+impl RestartPolicy {
+    pub fn builder() -> RestartPolicyBuilder {
+        RestartPolicyBuilder::default()
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureReason {
+    /// The server process exited.
+    Exited(ExitStatus),
+    /// The server process is running, but stopped answering requests.
+    Unresponsive,
+}
+
+#[derive(Debug, Clone)]
+pub enum RestartEvent {
+    /// The running server failed and will be restarted.
+    Failed { reason: FailureReason },
+    /// A restart will be attempted after `backoff`.
+    Restarting { attempt: u32, backoff: Duration },
+    /// A new server is online at `url`.
+    Restarted { attempt: u32, url: Url },
+    /// Spawning a new server failed. Another attempt will follow if the policy
+    /// allows it.
+    RestartFailed { attempt: u32, error: String },
+    /// The restart limit was reached, and the supervisor will not restart the
+    /// server again.
+    GaveUp { restarts: u32 },
+}
+
+/// A [`ViperServer`] that is restarted when it crashes or stops answering.
+///
+/// Verifications started through the supervisor are re-issued against the new
+/// server when the one they were sent to fails.
+#[derive(Debug, Clone)]
+pub struct Supervisor {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Generation {
+    id: u64,
+    client: Client,
+}
+
+impl Generation {
+    fn server(&self) -> &ViperServer {
+        self.client
+            .server
+            .as_ref()
+            .expect("supervised clients own their server")
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    viper_server_jar: PathBuf,
+    jvm: JvmConfig,
+    config: ServerConfig,
+    opts: ViperServerOpts,
+    policy: RestartPolicy,
+    current: RwLock<Arc<Generation>>,
+    next_generation: AtomicU64,
+    restarts: AtomicU32,
+    restart_lock: tokio::sync::Mutex<()>,
+    events: broadcast::Sender<RestartEvent>,
+    /// Dropped together with the supervisor, which stops the monitors
+    closed: watch::Sender<()>,
+}
+
+impl Supervisor {
+    pub async fn spawn(
+        viper_server_jar: impl AsRef<Path>,
+        jvm: &JvmConfig,
+        opts: ViperServerOpts,
+        policy: RestartPolicy,
+    ) -> Result<Supervisor> {
+        Self::spawn_configured(
+            viper_server_jar,
+            jvm,
+            &ServerConfig::default(),
+            opts,
+            policy,
+        )
+        .await
+    }
+
+    pub async fn spawn_configured(
+        viper_server_jar: impl AsRef<Path>,
+        jvm: &JvmConfig,
+        config: &ServerConfig,
+        mut opts: ViperServerOpts,
+        policy: RestartPolicy,
+    ) -> Result<Supervisor> {
+        opts.server_mode = Some("HTTP".to_string());
+        let viper_server_jar = viper_server_jar.as_ref().to_path_buf();
+        let server =
+            ViperServer::spawn_configured(&viper_server_jar, jvm, config, opts.clone()).await?;
+        let client = Client::new(server).await?;
+
+        let inner = Arc::new(Inner {
+            viper_server_jar,
+            jvm: jvm.clone(),
+            config: config.clone(),
+            opts,
+            policy,
+            current: RwLock::new(Arc::new(Generation { id: 0, client })),
+            next_generation: AtomicU64::new(1),
+            restarts: AtomicU32::new(0),
+            restart_lock: Default::default(),
+            events: broadcast::channel(64).0,
+            closed: watch::channel(()).0,
+        });
+        inner.monitor(inner.current());
+
+        Ok(Supervisor { inner })
+    }
+
+    /// Receive an event whenever the server fails or is restarted.
+    pub fn subscribe(&self) -> broadcast::Receiver<RestartEvent> {
+        self.inner.events.subscribe()
+    }
+
+    /// The number of times the server has been restarted since it was last
+    /// healthy for [`RestartPolicy::reset_after`].
+    pub fn restarts(&self) -> u32 {
+        self.inner.restarts.load(Ordering::SeqCst)
+    }
+
+    /// The URL the current server is reachable at.
+    pub fn base_url(&self) -> Url {
+        self.inner.current().client.base_url().clone()
+    }
+
+    /// Send a request. If the server fails before answering a request that
+    /// [starts a job](ViperRequest::starts_job), the server is restarted and
+    /// the request sent again. Other requests fail with the error.
+    pub async fn post<R: ViperRequest + Clone>(&self, body: R) -> Result<R::Response> {
+        loop {
+            let generation = self.inner.current();
+            match generation.client.post(body.clone()).await {
+                Err(err) if !body.starts_job() => return Err(err),
+                Err(err) => match self.inner.failure(&generation, &err).await {
+                    Some(reason) => {
+                        self.inner.restart(generation.id, reason).await?;
+                    }
+                    None => return Err(err),
+                },
+                res => return res,
+            }
+        }
+    }
+
+    /// Verify `request` and stream its results.
+    ///
+    /// If the server fails during the verification, it is restarted and the
+    /// verification is issued again from the beginning, so statuses that
+    /// were already received may be repeated.
+    pub fn verify(
+        &self,
+        request: VerificationRequest,
+    ) -> impl Stream<Item = Result<VerificationStatus>> + Send + 'static {
+        enum State {
+            Start,
+            Streaming {
+                generation: Arc<Generation>,
+                stream: BoxStream<'static, Result<VerificationStatus>>,
+            },
+            Done,
+        }
+
+        let inner = self.inner.clone();
+        futures::stream::unfold(State::Start, move |mut state| {
+            let inner = inner.clone();
+            let request = request.clone();
+            async move {
+                loop {
+                    let (generation, err) = match state {
+                        State::Start => {
+                            let generation = inner.current();
+                            let stream = async {
+                                let response = generation.client.post(request.clone()).await?;
+                                generation.client.check_on_verification(&response).await
+                            };
+                            match stream.await {
+                                Ok(stream) => {
+                                    state = State::Streaming {
+                                        generation,
+                                        stream: stream.boxed(),
+                                    };
+                                    continue;
+                                }
+                                Err(err) => (generation, err),
+                            }
+                        }
+                        State::Streaming {
+                            generation,
+                            mut stream,
+                        } => match stream.next().await {
+                            Some(Ok(status)) => {
+                                return Some((Ok(status), State::Streaming { generation, stream }))
+                            }
+                            Some(Err(err)) => (generation, err),
+                            None => return None,
+                        },
+                        State::Done => return None,
+                    };
+
+                    let Some(reason) = inner.failure(&generation, &err).await else {
+                        return Some((Err(err), State::Done));
+                    };
+                    if let Err(err) = inner.restart(generation.id, reason).await {
+                        return Some((Err(err), State::Done));
+                    }
+                    state = State::Start;
+                }
+            }
+        })
+    }
+}
+
+impl Inner {
+    fn current(&self) -> Arc<Generation> {
+        self.current.read().unwrap().clone()
+    }
+
+    fn emit(&self, event: RestartEvent) {
+        let _ = self.events.send(event);
+    }
+
+    /// Decide whether `err` was caused by the server failing, rather than by
+    /// the request itself.
+    async fn failure(
+        &self,
+        generation: &Generation,
+        err: &ViperServerError,
+    ) -> Option<FailureReason> {
+        let idle = match err {
+            ViperServerError::Network { .. } | ViperServerError::Io { .. } => false,
+            ViperServerError::StreamIdle { .. } => true,
+            _ => return None,
+        };
+        if let Ok(Some(status)) = generation.server().try_wait() {
+            Some(FailureReason::Exited(status))
+        } else if idle {
+            // A server that sent nothing for a whole idle timeout is stuck on
+            // the job, even if it still answers other requests
+            Some(FailureReason::Unresponsive)
+        } else if !responsive(&generation.client, self.policy.health_check_timeout).await {
+            Some(FailureReason::Unresponsive)
+        } else {
+            None
+        }
+    }
+
+    /// Watch `generation` until its server fails, and restart it when it does.
+    fn monitor(self: &Arc<Self>, generation: Arc<Generation>) {
+        let inner: Weak<Inner> = Arc::downgrade(self);
+        let mut closed = self.closed.subscribe();
+        let policy = self.policy.clone();
+
+        tokio::spawn(async move {
+            let mut failed_checks = 0;
+            let mut healthy_since = Instant::now();
+            let reason = loop {
+                tokio::select! {
+                    _ = closed.changed() => return,
+                    status = generation.server().wait() => match status {
+                        Ok(status) => break FailureReason::Exited(status),
+                        Err(_) => return,
+                    },
+                    _ = tokio::time::sleep(policy.health_check_interval) => {
+                        if responsive(&generation.client, policy.health_check_timeout).await {
+                            failed_checks = 0;
+                            if healthy_since.elapsed() >= policy.reset_after {
+                                let Some(inner) = inner.upgrade() else { return };
+                                inner.restarts.store(0, Ordering::SeqCst);
+                            }
+                        } else {
+                            failed_checks += 1;
+                            healthy_since = Instant::now();
+                            if failed_checks >= policy.unresponsive_after {
+                                break FailureReason::Unresponsive;
+                            }
+                        }
+                    }
+                }
+            };
+            if let Some(inner) = inner.upgrade() {
+                let _ = inner.restart(generation.id, reason).await;
+            }
+        });
+    }
+
+    /// Replace the server of generation `failed`, unless that already happened.
+    async fn restart(
+        self: &Arc<Self>,
+        failed: u64,
+        reason: FailureReason,
+    ) -> Result<Arc<Generation>> {
+        let _guard = self.restart_lock.lock().await;
+
+        let old = self.current();
+        if old.id != failed {
+            return Ok(old);
+        }
+        self.emit(RestartEvent::Failed { reason });
+        old.server().kill();
+
+        loop {
+            let attempt = self.restarts.load(Ordering::SeqCst) + 1;
+            if attempt > self.policy.max_restarts {
+                self.emit(RestartEvent::GaveUp {
+                    restarts: attempt - 1,
+                });
+                return Err(ViperServerError::RestartLimitReached {
+                    restarts: attempt - 1,
+                });
+            }
+            self.restarts.store(attempt, Ordering::SeqCst);
+
+            let backoff = self.policy.backoff(attempt);
+            self.emit(RestartEvent::Restarting { attempt, backoff });
+            tokio::time::sleep(backoff).await;
+
+            let spawned = async {
+                let server = ViperServer::spawn_configured(
+                    &self.viper_server_jar,
+                    &self.jvm,
+                    &self.config,
+                    self.opts.clone(),
+                )
+                .await?;
+                Client::new(server).await
+            };
+            match spawned.await {
+                Ok(client) => {
+                    let url = client.base_url().clone();
+                    let generation = Arc::new(Generation {
+                        id: self.next_generation.fetch_add(1, Ordering::SeqCst),
+                        client,
+                    });
+                    *self.current.write().unwrap() = generation.clone();
+                    self.monitor(generation.clone());
+                    self.emit(RestartEvent::Restarted { attempt, url });
+                    return Ok(generation);
+                }
+                Err(err) => self.emit(RestartEvent::RestartFailed {
+                    attempt,
+                    error: err.to_string(),
+                }),
+            }
+        }
+    }
+}
+
+/// Whether the server answers HTTP requests at all.
+async fn responsive(client: &Client, timeout: Duration) -> bool {
+    let check = client
+        .http()
+        .send(Method::GET, client.base_url().clone(), |b| b);
+    matches!(tokio::time::timeout(timeout, check).await, Ok(Ok(_)))
+}
@@ -0,0 +1,391 @@
+//! A mock ViperServer, for testing code built on [`Client`] without Java, a
+//! `viperserver.jar` or Z3.
+//!
+//! The [`MockServer`] speaks ViperServer's HTTP protocol, and answers each
+//! verification with the [`Script`] registered for its file.
+//!
+//! ```no_run
+//! # async fn run() -> viperserver::error::Result<()> {
+//! use viperserver::{testing::{MockServer, Script}, VerificationRequest};
+//!
+//! let server = MockServer::start()?;
+//! server.script("hello.vpr", Script::verified());
+//!
+//! let client = server.client().await?;
+//! let request = VerificationRequest::silicon().verify_file("hello.vpr")?;
+//! assert!(client.verify(request).await?.is_success());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use hyper::{
+    body::Bytes,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, StatusCode,
+};
+use serde_json::json;
+use tokio::{sync::oneshot, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    args,
+    client::Client,
+    error::Result,
+    verification::{Backend, Details, VerificationStatus},
+};
+
+/// What the server sends in answer to one verification.
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    steps: Vec<Step>,
+    reject: Option<(StatusCode, String)>,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Status(Box<VerificationStatus>),
+    Line(String),
+    Delay(Duration),
+    Disconnect,
+}
+
+impl Script {
+    pub fn new() -> Script {
+        Script::default()
+    }
+
+    /// A program that parses, typechecks and verifies.
+    pub fn verified() -> Script {
+        Script::new()
+            .status(ast_construction("success"))
+            .status(overall("success", vec![]))
+    }
+
+    /// A program with the given verification errors, such as
+    /// `("assert.failed:assertion.false", "Assertion false might not hold.")`.
+    pub fn failed<'a>(errors: impl IntoIterator<Item = (&'a str, &'a str)>) -> Script {
+        let errors = errors
+            .into_iter()
+            .map(|(tag, text)| {
+                json!({
+                    "cached": false,
+                    "position": "<no position>",
+                    "tag": tag,
+                    "text": text,
+                })
+            })
+            .collect();
+        Script::new()
+            .status(ast_construction("success"))
+            .status(overall("failure", errors))
+    }
+
+    /// Send `status`.
+    pub fn status(mut self, status: VerificationStatus) -> Script {
+        self.steps.push(Step::Status(Box::new(status)));
+        self
+    }
+
+    /// Send `line` as is, such as a malformed status.
+    pub fn line(mut self, line: impl Into<String>) -> Script {
+        self.steps.push(Step::Line(line.into()));
+        self
+    }
+
+    /// Wait before sending the next status.
+    pub fn delay(mut self, delay: Duration) -> Script {
+        self.steps.push(Step::Delay(delay));
+        self
+    }
+
+    /// Break off the stream of statuses.
+    pub fn disconnect(mut self) -> Script {
+        self.steps.push(Step::Disconnect);
+        self
+    }
+
+    /// Refuse to start the verification, answering with `status` and `body`.
+    pub fn reject(mut self, status: StatusCode, body: impl Into<String>) -> Script {
+        self.reject = Some((status, body.into()));
+        self
+    }
+}
+
+fn ast_construction(status: &str) -> VerificationStatus {
+    VerificationStatus::AstConstructionResult {
+        details: details(None),
+        status: status.to_string(),
+    }
+}
+
+fn overall(status: &str, errors: Vec<serde_json::Value>) -> VerificationStatus {
+    let result = (!errors.is_empty()).then(|| json!({ "errors": errors, "type": "error" }));
+    VerificationStatus::VerificationResult {
+        details: details(result),
+        kind: "overall".to_string(),
+        status: status.to_string(),
+        verifier: Backend::Silicon,
+        extra: HashMap::new(),
+    }
+}
+
+fn details(result: Option<serde_json::Value>) -> Details {
+    serde_json::from_value(json!({ "time": 0, "result": result }))
+        .expect("mock details are well-formed")
+}
+
+#[derive(Debug, Default)]
+struct State {
+    scripts: Vec<(String, Script)>,
+    default_script: Option<Script>,
+    next_id: u64,
+    jobs: HashMap<u64, Job>,
+    requests: Vec<Vec<String>>,
+    discarded: Vec<u64>,
+    exited: bool,
+}
+
+#[derive(Debug)]
+struct Job {
+    script: Script,
+    cancel: CancellationToken,
+}
+
+/// A mock ViperServer listening on a local port. Stops when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Start a server on a free local port. Must be called within a Tokio
+    /// runtime.
+    pub fn start() -> Result<MockServer> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+
+        let state = Arc::new(Mutex::new(State::default()));
+        let (shutdown, shutdown_rx) = oneshot::channel();
+
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let state = state.clone();
+                    async move { Ok::<_, Infallible>(handle(&state, req).await) }
+                }))
+            }
+        });
+        let server = hyper::Server::from_tcp(listener)
+            .map_err(std::io::Error::other)?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        let task = tokio::spawn(async move {
+            let _ = server.await;
+        });
+
+        Ok(MockServer {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+            task,
+        })
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    /// A client connected to the server.
+    pub async fn client(&self) -> Result<Client> {
+        Client::connect(self.url()).await
+    }
+
+    /// Answer verifications of files whose path ends with `file` with
+    /// `script`. Scripts registered first take precedence.
+    pub fn script(&self, file: impl Into<String>, script: Script) -> &Self {
+        self.state().scripts.push((file.into(), script));
+        self
+    }
+
+    /// Answer verifications of files without a script of their own with
+    /// `script`. Without one, such verifications are rejected.
+    pub fn default_script(&self, script: Script) -> &Self {
+        self.state().default_script = Some(script);
+        self
+    }
+
+    /// The arguments of the verifications started so far.
+    pub fn requests(&self) -> Vec<Vec<String>> {
+        self.state().requests.clone()
+    }
+
+    /// The ids of the jobs discarded so far.
+    pub fn discarded(&self) -> Vec<u64> {
+        self.state().discarded.clone()
+    }
+
+    /// Whether the server was asked to exit.
+    pub fn exited(&self) -> bool {
+        self.state().exited
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        self.task.abort();
+    }
+}
+
+fn respond(status: StatusCode, body: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("mock responses are well-formed")
+}
+
+fn message(msg: impl Into<String>) -> Response<Body> {
+    respond(StatusCode::OK, json!({ "msg": msg.into() }))
+}
+
+async fn handle(state: &Mutex<State>, req: Request<Body>) -> Response<Body> {
+    let method = req.method().clone();
+    let path = req.uri().path().trim_start_matches('/').to_string();
+    let segments: Vec<&str> = path.split('/').collect();
+    match (method, segments.as_slice()) {
+        (Method::POST, ["verify"]) => {
+            let body = hyper::body::to_bytes(req.into_body())
+                .await
+                .unwrap_or_default();
+            start(state, &body)
+        }
+        (Method::GET, ["verify", id]) => match id.parse().ok().and_then(|id| {
+            let state = state.lock().unwrap();
+            let job = state.jobs.get(&id)?;
+            Some((job.script.clone(), job.cancel.clone()))
+        }) {
+            Some((script, cancel)) => stream(script, cancel),
+            None => message(format!("Verification request with id {id} not found")),
+        },
+        (Method::GET, ["discard", id]) => {
+            let mut state = state.lock().unwrap();
+            match id.parse().ok().filter(|id| state.jobs.contains_key(id)) {
+                Some(id) => {
+                    state.jobs[&id].cancel.cancel();
+                    state.discarded.push(id);
+                    message(format!("Discarded job {id}"))
+                }
+                None => message(format!("Verification request with id {id} not found")),
+            }
+        }
+        (Method::GET, ["exit"]) => {
+            state.lock().unwrap().exited = true;
+            message("shutting down...")
+        }
+        (_, ["cache", "flush"]) => message("The cache has been flushed successfully."),
+        _ => respond(StatusCode::NOT_FOUND, json!({ "msg": "not found" })),
+    }
+}
+
+/// Start a job for the verification request in `body`.
+fn start(state: &Mutex<State>, body: &[u8]) -> Response<Body> {
+    let arg = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|body| body.get("arg")?.as_str().map(str::to_string));
+    let Some(arg) = arg else {
+        return respond(
+            StatusCode::BAD_REQUEST,
+            json!({ "msg": "expected a verification request" }),
+        );
+    };
+    let args = args::split(&arg);
+    let file = args.last().cloned().unwrap_or_default();
+
+    let mut state = state.lock().unwrap();
+    state.requests.push(args);
+    let script = state
+        .scripts
+        .iter()
+        .find(|(pattern, _)| Path::new(&file).ends_with(pattern))
+        .map(|(_, script)| script)
+        .or(state.default_script.as_ref())
+        .cloned();
+    let Some(script) = script else {
+        return respond(
+            StatusCode::BAD_REQUEST,
+            json!({ "msg": format!("no script for {file:?}") }),
+        );
+    };
+    if let Some((status, body)) = &script.reject {
+        return respond(*status, json!({ "msg": body }));
+    }
+
+    state.next_id += 1;
+    let id = state.next_id;
+    state.jobs.insert(
+        id,
+        Job {
+            script,
+            cancel: CancellationToken::new(),
+        },
+    );
+    respond(StatusCode::OK, json!({ "ast_id": id, "id": id }))
+}
+
+/// Stream the statuses of `script`, one JSON object per line, until it ends
+/// or the job is discarded.
+fn stream(script: Script, cancel: CancellationToken) -> Response<Body> {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        for step in script.steps {
+            let line = match step {
+                Step::Status(status) => serde_json::to_string(&status).expect("statuses serialize"),
+                Step::Line(line) => line,
+                Step::Delay(delay) => {
+                    tokio::select! {
+                        _ = cancel.cancelled() => return,
+                        _ = tokio::time::sleep(delay) => continue,
+                    }
+                }
+                Step::Disconnect => {
+                    sender.abort();
+                    return;
+                }
+            };
+            if cancel.is_cancelled() {
+                return;
+            }
+            if sender.send_data(Bytes::from(line + "\n")).await.is_err() {
+                return;
+            }
+        }
+    });
+    Response::builder()
+        .header("content-type", "application/x-ndjson")
+        .body(body)
+        .expect("mock responses are well-formed")
+}
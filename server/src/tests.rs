@@ -1,8 +1,29 @@
-use std::{fs, path::PathBuf, process::Command};
-
-use crate::{client, server, verification::VerificationStatus};
+#[cfg(unix)]
+use crate::jvm::JvmConfig;
+use crate::{client, jar::ViperServerJar, server, verification::VerificationStatus};
 
+mod args;
+mod blocking;
 mod generate_cli;
+mod http;
+mod jar;
+mod jvm;
+mod logging;
+mod lsp;
+mod mock;
+mod outcome;
+mod output;
+mod paths;
+mod pool;
+mod program;
+mod progress;
+mod record;
+mod requests;
+mod shutdown;
+mod startup;
+mod supervisor;
+mod tags;
+mod toolchain;
 
 #[tokio::test]
 async fn basic_test() -> color_eyre::Result<()> {
@@ -10,18 +31,9 @@ async fn basic_test() -> color_eyre::Result<()> {
 
     color_eyre::install()?;
 
-    #[cfg(feature = "bundle-viperserver")]
-    let viperserver = crate::bundled::ViperServerJar::create()?;
-    #[cfg(not(feature = "bundle-viperserver"))]
-    let viperserver = {
-        let viperserver_path = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/viperserver/"));
-        let target_path = fs::read_dir(viperserver_path.join("target/"))?
-            .map(|p| p.unwrap())
-            .find(|p| p.file_name().to_str().unwrap().starts_with("scala-"))
-            .unwrap()
-            .path();
-        target_path.join("viperserver.jar")
-    };
+    let viperserver = ViperServerJar::discover().or_else(|_| {
+        ViperServerJar::from_build_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/viperserver/"))
+    })?;
 
     eprintln!("Using viperserver at {viperserver:?}");
 
@@ -54,12 +66,30 @@ async fn basic_test() -> color_eyre::Result<()> {
         }
     }
 
-    while let Ok(r) = client.server.stdout.try_recv() {
-        eprintln!("stdout: {r:?}");
-    }
-    while let Ok(r) = client.server.stderr.try_recv() {
-        eprintln!("stderr: {r:?}");
+    if let Some(server) = &mut client.server {
+        for r in server.stdout.recent() {
+            eprintln!("stdout: {r:?}");
+        }
+        for r in server.stderr.recent() {
+            eprintln!("stderr: {r:?}");
+        }
     }
 
     Ok(())
 }
+
+/// A `java` that runs `script` instead of ViperServer, for testing how servers
+/// are started, supervised and shut down.
+#[cfg(unix)]
+fn fake_java(dir: &std::path::Path, script: &str) -> std::io::Result<JvmConfig> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("java");
+    std::fs::write(&path, format!("#!/bin/sh\n{script}\n"))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(JvmConfig::builder()
+        .java(path)
+        .check_version(false)
+        .build()
+        .expect("all fields have defaults"))
+}
@@ -0,0 +1,105 @@
+use crate::{
+    args::{self, ArgsError},
+    client::VerificationRequest,
+};
+
+#[test]
+fn quote_only_when_needed() {
+    assert_eq!(args::quote("--timeout").unwrap(), "--timeout");
+    assert_eq!(args::quote("a b").unwrap(), "\"a b\"");
+    assert_eq!(args::quote("").unwrap(), "\"\"");
+    assert_eq!(args::quote("it's").unwrap(), "\"it's\"");
+    assert_eq!(args::quote("say \"hi\"").unwrap(), "'say \"hi\"'");
+    assert!(matches!(
+        args::quote("it's \"both\""),
+        Err(ArgsError::Unrepresentable { .. })
+    ));
+}
+
+#[test]
+fn split_like_viperserver() {
+    assert_eq!(
+        args::split(r#"silicon --z3Args "smt.qi.eager_threshold=100 x" 'a "b"' c"d" e"#),
+        [
+            "silicon",
+            "--z3Args",
+            "smt.qi.eager_threshold=100 x",
+            "a \"b\"",
+            "c",
+            "d",
+            "e"
+        ]
+    );
+    // An unmatched quote is dropped
+    assert_eq!(args::split("a \"b c"), ["a", "b", "c"]);
+    assert_eq!(args::split("  \t\n"), Vec::<String>::new());
+}
+
+#[test]
+fn awkward_arguments_round_trip() {
+    let awkward = [
+        "plain",
+        "/tmp/with space/prog.vpr",
+        "C:\\Users\\me\\My Programs\\a.vpr",
+        "/tmp/it's here/a.vpr",
+        "/tmp/\"quoted\"/a.vpr",
+        "/tmp/ünïcødé ✓/a.vpr",
+        "tab\tand\x0Bvertical tab",
+        "",
+        "--proverArgs=smt.qi.eager_threshold=100 smt.mbqi=false",
+    ];
+    let line = args::encode(awkward).unwrap();
+    assert_eq!(args::split(&line), awkward);
+}
+
+#[test]
+fn line_breaks_are_refused() {
+    for arg in ["a\nb", "a\r\nb", "a\u{2028}b", "\n"] {
+        assert_eq!(
+            args::quote(arg),
+            Err(ArgsError::LineBreak {
+                arg: arg.to_string()
+            })
+        );
+    }
+    assert!(matches!(
+        args::encode(["silicon", "/tmp/new\nline.vpr"]),
+        Err(ArgsError::LineBreak { .. })
+    ));
+}
+
+#[test]
+fn verification_requests_round_trip() {
+    let file = "/home/me/Viper Programs/it's a test ✓.vpr";
+    let request = VerificationRequest::silicon()
+        .z3_args("smt.qi.eager_threshold=100 smt.mbqi=false")
+        .verify_file(file)
+        .unwrap();
+    let args = args::split(&request.command_line().unwrap());
+    assert_eq!(args.first().map(String::as_str), Some("silicon"));
+    assert_eq!(args.last().map(String::as_str), Some(file));
+    let z3_args = args.iter().position(|a| a == "--z3Args").unwrap();
+    assert_eq!(
+        args[z3_args + 1],
+        "smt.qi.eager_threshold=100 smt.mbqi=false"
+    );
+    assert_eq!(args, request.args().unwrap());
+}
+
+#[test]
+fn custom_backend_request() {
+    let request = VerificationRequest::custom(
+        "viper.silicon.PatchedSilicon",
+        [
+            "--timeout",
+            "10",
+            "--z3Args",
+            "smt.mbqi=false smt.qi.eager_threshold=100",
+        ],
+        "/tmp/my program.vpr",
+    );
+    assert_eq!(
+        request.command_line().unwrap(),
+        r#"viper.silicon.PatchedSilicon --timeout 10 --z3Args "smt.mbqi=false smt.qi.eager_threshold=100" "/tmp/my program.vpr""#
+    );
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "blocking")]
+
+use crate::{
+    blocking::{Client, VerificationRequest, VerificationStatus},
+    testing::{MockServer, Script},
+};
+
+fn request(file: &str) -> VerificationRequest {
+    VerificationRequest::silicon()
+        .timeout("10")
+        .verify_file(file)
+        .unwrap()
+}
+
+/// A mock server on a runtime of its own, as blocking calls must not be made
+/// from within one.
+fn mock_server() -> std::io::Result<(MockServer, tokio::runtime::Runtime)> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let server = {
+        let _guard = runtime.enter();
+        MockServer::start()
+    }
+    .map_err(std::io::Error::other)?;
+    Ok((server, runtime))
+}
+
+#[test]
+fn verify_without_a_runtime() -> color_eyre::Result<()> {
+    let (server, _runtime) = mock_server()?;
+    server.script("ok.vpr", Script::verified()).script(
+        "bad.vpr",
+        Script::failed([("assert.failed:assertion.false", "Assertion might not hold.")]),
+    );
+    let client = Client::connect(server.url())?;
+
+    assert!(client.verify(request("ok.vpr"))?.is_success());
+    let outcome = client.verify(request("bad.vpr"))?;
+    assert_eq!(outcome.errors().count(), 1);
+
+    let statuses = client
+        .verify_stream(request("ok.vpr"))?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert!(matches!(
+        statuses.last(),
+        Some(VerificationStatus::VerificationResult { .. })
+    ));
+
+    client.exit()?;
+    assert!(server.exited());
+    Ok(())
+}
+
+#[tokio::test]
+#[should_panic(expected = "from within a runtime")]
+async fn blocking_calls_panic_within_a_runtime() {
+    let server = MockServer::start().unwrap();
+    let _ = Client::connect(server.url());
+}
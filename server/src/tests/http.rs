@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::{
+    client::{Client, VerificationResponse},
+    error::ViperServerError,
+    http::HttpConfigBuilder,
+    testing::MockServer,
+};
+
+/// Read a request and return its path, ignoring the body.
+async fn read_request(stream: &mut BufReader<TcpStream>) -> String {
+    let mut line = String::new();
+    stream.read_line(&mut line).await.unwrap();
+    let path = line.split(' ').nth(1).unwrap().to_string();
+    loop {
+        let mut header = String::new();
+        stream.read_line(&mut header).await.unwrap();
+        if header.trim_end().is_empty() {
+            return path;
+        }
+    }
+}
+
+async fn respond(stream: &mut BufReader<TcpStream>, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await.unwrap();
+}
+
+#[tokio::test]
+async fn idempotent_requests_are_retried() -> color_eyre::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        // The handshake of `connect`
+        read_request(&mut stream).await;
+        respond(&mut stream, "200 OK", r#"{"msg":"unknown job"}"#).await;
+
+        assert_eq!(read_request(&mut stream).await, "/cache/flush");
+        respond(&mut stream, "503 Service Unavailable", "").await;
+        assert_eq!(read_request(&mut stream).await, "/cache/flush");
+        respond(&mut stream, "200 OK", r#"{"msg":"flushed"}"#).await;
+    });
+
+    let http = HttpConfigBuilder::default()
+        .retry_delay(Duration::from_millis(1))
+        .build()?;
+    let client = Client::connect_with(format!("http://{addr}"), &http).await?;
+    let flushed = client.flush_cache().await?;
+    assert_eq!(flushed.msg, "flushed");
+
+    server.await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn handshake_leaves_the_server_alone() -> color_eyre::Result<()> {
+    let mock = MockServer::start()?;
+    Client::connect(mock.url()).await?;
+    assert!(mock.discarded().is_empty());
+    assert!(!mock.exited());
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        assert_eq!(read_request(&mut stream).await, "/verify/2147483647");
+        respond(&mut stream, "200 OK", "<html>It works!</html>").await;
+    });
+    assert!(matches!(
+        Client::connect(format!("http://{addr}")).await,
+        Err(ViperServerError::NotAViperServer { .. })
+    ));
+
+    server.await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn idle_stream_times_out() -> color_eyre::Result<()> {
+    use futures::StreamExt;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut stream = BufReader::new(stream);
+        read_request(&mut stream).await;
+        respond(&mut stream, "200 OK", r#"{"msg":"unknown job"}"#).await;
+
+        assert_eq!(read_request(&mut stream).await, "/verify/3");
+        let line = "{\"msg_type\":\"copyright_report\",\"msg_body\":{\"text\":\"(c)\"}}\n";
+        let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+        let chunk = format!("{:x}\r\n{line}\r\n", line.len());
+        stream.write_all(head.as_bytes()).await.unwrap();
+        stream.write_all(chunk.as_bytes()).await.unwrap();
+        // Then go quiet, keeping the connection open
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let http = HttpConfigBuilder::default()
+        .idle_timeout(Duration::from_millis(200))
+        .build()?;
+    let client = Client::connect_with(format!("http://{addr}"), &http).await?;
+    let response: VerificationResponse = serde_json::from_str(r#"{"ast_id":0,"id":3}"#)?;
+    let mut stream = client.check_on_verification(&response).await?;
+
+    assert!(stream.next().await.unwrap().is_ok());
+    assert!(matches!(
+        stream.next().await,
+        Some(Err(ViperServerError::StreamIdle { .. }))
+    ));
+    assert!(stream.next().await.is_none());
+
+    server.abort();
+    Ok(())
+}
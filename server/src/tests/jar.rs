@@ -0,0 +1,28 @@
+use crate::jar::{JarSource, JarVersions, ViperServerJar};
+
+#[test]
+fn parse_jar_versions() {
+    let versions = JarVersions::parse(
+        "ViperServer 2.0.0 (4f3c1e2)\nSilicon: 1.1-SNAPSHOT\ncarbon version 1.0, built today\n",
+    );
+    assert_eq!(versions.viperserver.as_deref(), Some("2.0.0"));
+    assert_eq!(versions.silicon.as_deref(), Some("1.1-SNAPSHOT"));
+    assert_eq!(versions.carbon.as_deref(), Some("1.0"));
+
+    assert_eq!(JarVersions::parse("unknown option").viperserver, None);
+}
+
+#[test]
+fn jar_from_build_dir() -> color_eyre::Result<()> {
+    let checkout = tempfile::tempdir()?;
+    assert!(ViperServerJar::from_build_dir(checkout.path()).is_err());
+
+    let target = checkout.path().join("target/scala-2.13");
+    std::fs::create_dir_all(&target)?;
+    std::fs::write(target.join("viperserver.jar"), "")?;
+
+    let jar = ViperServerJar::from_build_dir(checkout.path())?;
+    assert_eq!(jar.path(), target.join("viperserver.jar"));
+    assert_eq!(jar.source(), JarSource::Build);
+    Ok(())
+}
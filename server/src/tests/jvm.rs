@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use crate::jvm::{JavaVersion, JvmConfig, StagedArchive};
+
+#[test]
+fn parse_java_versions() {
+    let major = |output: &str| JavaVersion::parse(output).map(|v| v.major);
+
+    assert_eq!(
+        major("java version \"1.8.0_292\"\nJava(TM) SE Runtime Environment (build 1.8.0_292-b10)"),
+        Some(8)
+    );
+    assert_eq!(
+        major("openjdk version \"11.0.2\" 2019-01-15\nOpenJDK Runtime Environment 18.9"),
+        Some(11)
+    );
+    assert_eq!(major("openjdk version \"17\" 2021-09-14"), Some(17));
+    assert_eq!(major("openjdk version \"21-ea\" 2023-09-19"), Some(21));
+    assert_eq!(
+        JavaVersion::parse("openjdk version \"21-ea\" 2023-09-19")
+            .unwrap()
+            .full,
+        "21-ea"
+    );
+    assert_eq!(major("command not found: java"), None);
+}
+
+fn server_args(jvm: &JvmConfig, java: Option<u32>) -> Vec<String> {
+    let java = java.map(|major| JavaVersion {
+        major,
+        full: major.to_string(),
+    });
+    let (cmd, _) = jvm.server_command(Path::new("viperserver.jar"), java.as_ref());
+    cmd.as_std()
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn class_data_archive_needs_java_13() -> color_eyre::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let archive = dir.path().join("viperserver.jsa");
+    let jvm = JvmConfig::builder().class_data_archive(&archive).build()?;
+    let uses_archive = |args: Vec<String>| args.iter().any(|a| a.starts_with("-XX:"));
+
+    assert!(!uses_archive(server_args(&jvm, Some(11))));
+    assert!(!uses_archive(server_args(&jvm, Some(12))));
+    assert!(!uses_archive(server_args(&jvm, None)));
+
+    // A missing archive is written to a file of the JVM's own
+    let args = server_args(&jvm, Some(13));
+    let dump = args
+        .iter()
+        .find_map(|a| a.strip_prefix("-XX:ArchiveClassesAtExit="))
+        .expect("archive flag");
+    assert_ne!(Path::new(dump), archive);
+    assert_eq!(Path::new(dump).parent(), Some(dir.path()));
+    assert_ne!(server_args(&jvm, Some(17)), args);
+
+    std::fs::write(&archive, "")?;
+    assert!(server_args(&jvm, Some(17))
+        .contains(&format!("-XX:SharedArchiveFile={}", archive.display())));
+    Ok(())
+}
+
+#[test]
+fn staged_archives_replace_the_archive_on_exit() -> color_eyre::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let archive = dir.path().join("viperserver.jsa");
+
+    let staged = StagedArchive::new(&archive);
+    std::fs::write(&staged.staging, "killed")?;
+    let staging = staged.staging.clone();
+    staged.finish(false)?;
+    assert!(!staging.exists());
+    assert!(!archive.exists());
+
+    let staged = StagedArchive::new(&archive);
+    std::fs::write(&staged.staging, "exited")?;
+    staged.finish(true)?;
+    assert_eq!(std::fs::read_to_string(&archive)?, "exited");
+
+    // A JVM that exits without writing an archive leaves the old one alone
+    StagedArchive::new(&archive).finish(true)?;
+    assert_eq!(std::fs::read_to_string(&archive)?, "exited");
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use crate::{logging::LogLine, server::ViperServerLogLevel};
+
+#[test]
+fn parse_log_lines() {
+    let line = LogLine::parse(
+        "2023-03-01 12:00:00,123 [main] INFO  ViperServer - Verification of VerJobId(3) started",
+    );
+    assert_eq!(line.level, Some(ViperServerLogLevel::Info));
+    assert_eq!(line.logger, Some("ViperServer"));
+    assert_eq!(line.job_id, Some(3));
+    assert_eq!(line.message, "Verification of VerJobId(3) started");
+
+    let line = LogLine::parse("12:00:00.123 WARN Cache [Cache.scala:41] evicting job #12");
+    assert_eq!(line.level, Some(ViperServerLogLevel::Warn));
+    assert_eq!(line.logger, Some("Cache"));
+    assert_eq!(line.job_id, Some(12));
+    assert_eq!(line.message, "evicting job #12");
+
+    let line = LogLine::parse("Silicon found 2 errors in 1.23s");
+    assert_eq!(line.level, None);
+    assert_eq!(line.job_id, None);
+    assert_eq!(line.message, "Silicon found 2 errors in 1.23s");
+}
@@ -0,0 +1,159 @@
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+use crate::{
+    error::ViperServerError,
+    lsp::{LspClient, LspNotification, VerificationState, VerificationSuccess},
+    verification::OptionalPosition,
+};
+
+async fn read_frame<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Value {
+    let mut len = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).await.unwrap();
+        match header.trim_end().split_once(": ") {
+            Some((_, value)) => len = value.parse().unwrap(),
+            None => break,
+        }
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+fn frame(message: Value) -> Vec<u8> {
+    let body = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{body}", body.len()).into_bytes()
+}
+
+#[tokio::test]
+async fn lsp_handshake_and_state_change() -> color_eyre::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let (subscribed_tx, subscribed) = tokio::sync::oneshot::channel();
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut read = BufReader::new(read);
+
+        let initialize = read_frame(&mut read).await;
+        assert_eq!(initialize["method"], "initialize");
+        write
+            .write_all(&frame(
+                json!({ "jsonrpc": "2.0", "id": initialize["id"], "result": {} }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(read_frame(&mut read).await["method"], "initialized");
+
+        write
+            .write_all(&frame(
+                json!({ "jsonrpc": "2.0", "id": 0, "method": "GetViperFileEndings" }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            read_frame(&mut read).await["result"]["fileEndings"],
+            json!(["*.vpr", "*.sil"])
+        );
+
+        subscribed.await.unwrap();
+        write
+            .write_all(&frame(json!({
+                "jsonrpc": "2.0",
+                "method": "StateChange",
+                "params": {
+                    "newState": 6,
+                    "success": 4,
+                    "verificationCompleted": 1,
+                    "progress": -1,
+                    "uri": "file:///a.vpr",
+                    "diagnostics": [{
+                        "range": {
+                            "start": { "line": 2, "character": 4 },
+                            "end": { "line": 2, "character": 9 },
+                        },
+                        "code": "assert.failed",
+                        "message": "Assertion might not hold.",
+                    }],
+                },
+            })))
+            .await
+            .unwrap();
+    });
+
+    let client = LspClient::connect(addr.to_string()).await?;
+    let mut notifications = client.notifications();
+    subscribed_tx.send(()).unwrap();
+    server.await?;
+
+    let LspNotification::StateChange(change) = notifications.recv().await? else {
+        panic!("expected a state change");
+    };
+    assert_eq!(change.new_state, VerificationState::Ready);
+    assert_eq!(
+        change.success,
+        Some(VerificationSuccess::VerificationFailed)
+    );
+    assert_eq!(change.verification_completed, Some(true));
+    assert_eq!(change.progress, None);
+
+    let errors = change.errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].tag, "assert.failed");
+    let OptionalPosition::Some(position) = &errors[0].position else {
+        panic!("expected a position");
+    };
+    assert_eq!(position.start, "3:5");
+    assert_eq!(position.file, "file:///a.vpr");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn requests_fail_once_the_server_stops_sending() -> color_eyre::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let (done_tx, done) = tokio::sync::oneshot::channel::<()>();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (read, mut write) = stream.into_split();
+        let mut read = BufReader::new(read);
+
+        let initialize = read_frame(&mut read).await;
+        write
+            .write_all(&frame(
+                json!({ "jsonrpc": "2.0", "id": initialize["id"], "result": {} }),
+            ))
+            .await
+            .unwrap();
+        read_frame(&mut read).await;
+
+        // Stop sending, but keep accepting whatever the client writes
+        write.shutdown().await.unwrap();
+        let _ = done.await;
+        drop(read);
+    });
+
+    let client = LspClient::connect(addr.to_string()).await?;
+    // Give the client time to notice the connection was closed
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        client.request::<_, Value>("shutdown", Value::Null),
+    )
+    .await?;
+    assert!(matches!(result, Err(ViperServerError::LspClosed)));
+
+    done_tx.send(()).unwrap();
+    server.await?;
+    Ok(())
+}
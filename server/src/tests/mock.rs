@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use crate::{
+    batch::{BatchConfigBuilder, BatchStatus},
+    client::{Client, VerificationRequest},
+    error::ViperServerError,
+    testing::{MockServer, Script},
+    verification::VerificationStatus,
+};
+
+fn request(file: &str) -> VerificationRequest {
+    VerificationRequest::silicon()
+        .timeout("10")
+        .verify_file(file)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn verify_scripted_outcomes() -> color_eyre::Result<()> {
+    let server = MockServer::start()?;
+    server.script("ok.vpr", Script::verified()).script(
+        "bad.vpr",
+        Script::failed([("assert.failed:assertion.false", "Assertion might not hold.")]),
+    );
+    let client = server.client().await?;
+
+    assert!(client.verify(request("/work/ok.vpr")).await?.is_success());
+
+    let outcome = client.verify(request("/work/bad.vpr")).await?;
+    assert!(!outcome.is_success());
+    assert_eq!(outcome.errors().count(), 1);
+
+    assert_eq!(
+        server.requests()[0],
+        ["silicon", "--timeout", "10", "/work/ok.vpr"]
+    );
+
+    client.exit().await?;
+    assert!(server.exited());
+    Ok(())
+}
+
+#[tokio::test]
+async fn malformed_and_broken_streams() -> color_eyre::Result<()> {
+    let server = MockServer::start()?;
+    server
+        .script("malformed.vpr", Script::verified().line("{not json"))
+        .script("broken.vpr", Script::new().disconnect())
+        .script(
+            "rejected.vpr",
+            Script::new().reject(hyper::StatusCode::SERVICE_UNAVAILABLE, "busy"),
+        );
+    let client = server.client().await?;
+
+    assert!(matches!(
+        client.verify(request("malformed.vpr")).await,
+        Err(ViperServerError::ParseJson { .. })
+    ));
+    assert!(client.verify(request("broken.vpr")).await.is_err());
+    assert!(client.post(request("rejected.vpr")).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_a_running_verification() -> color_eyre::Result<()> {
+    let server = MockServer::start()?;
+    server.default_script(Script::verified().delay(Duration::from_secs(30)).status(
+        VerificationStatus::CopyrightReport {
+            text: "never sent".to_string(),
+        },
+    ));
+    let client = server.client().await?;
+
+    let mut stream = client.verify_stream(request("slow.vpr")).await?;
+    stream.next().await.unwrap()?;
+    stream.next().await.unwrap()?;
+    stream.cancel();
+    assert!(matches!(
+        stream.next().await,
+        Some(Ok(VerificationStatus::Cancelled))
+    ));
+    assert!(stream.next().await.is_none());
+    assert_eq!(server.discarded(), [stream.id()]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_summary() -> color_eyre::Result<()> {
+    let server = MockServer::start()?;
+    server
+        .script(
+            "slow.vpr",
+            Script::new().delay(Duration::from_millis(200)).status(
+                VerificationStatus::AstConstructionResult {
+                    details: serde_json::from_str(r#"{"time": 0}"#)?,
+                    status: "success".to_string(),
+                },
+            ),
+        )
+        .script("bad.vpr", Script::failed([("tag", "error")]))
+        .script("broken.vpr", Script::verified().line("garbage"))
+        .default_script(Script::verified());
+    let client: Client = server.client().await?;
+
+    let files = ["a.vpr", "slow.vpr", "bad.vpr", "b.vpr", "broken.vpr"];
+    let config = BatchConfigBuilder::default()
+        .parallelism(2usize)
+        .slowest(1usize)
+        .build()?;
+    let mut batch = client.verify_batch(files.map(request), &config);
+
+    let mut statuses = vec![BatchStatus::Passed; files.len()];
+    while let Some(result) = batch.next().await {
+        statuses[result.index] = result.status();
+    }
+    assert_eq!(
+        statuses,
+        [
+            BatchStatus::Passed,
+            BatchStatus::Passed,
+            BatchStatus::Failed,
+            BatchStatus::Passed,
+            BatchStatus::Errored,
+        ]
+    );
+
+    let summary = batch.finish().await;
+    assert_eq!((summary.passed, summary.failed, summary.errored), (3, 1, 1));
+    assert_eq!(summary.slowest[0].0.to_str(), Some("slow.vpr"));
+    Ok(())
+}
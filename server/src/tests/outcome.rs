@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::{outcome::VerificationOutcome, verification::VerificationStatus};
+
+fn status(value: serde_json::Value) -> VerificationStatus {
+    serde_json::from_value(value).unwrap()
+}
+
+fn entity(name: &str, ty: &str) -> serde_json::Value {
+    json!({
+        "name": name,
+        "position": { "file": "a.vpr", "start": "1:1", "end": "3:1" },
+        "type": ty,
+    })
+}
+
+fn error(text: &str) -> serde_json::Value {
+    json!({
+        "cached": false,
+        "position": { "file": "a.vpr", "start": "2:3", "end": "2:15" },
+        "tag": "assert.failed:assertion.false",
+        "text": text,
+    })
+}
+
+#[test]
+fn collect_outcome_from_statuses() {
+    let outcome = VerificationOutcome::from_statuses([
+        status(json!({
+            "msg_type": "ast_construction_result",
+            "msg_body": { "details": { "time": 40 }, "status": "success" },
+        })),
+        status(json!({
+            "msg_type": "verification_result",
+            "msg_body": {
+                "details": { "time": 120, "cached": false, "entity": entity("f", "function") },
+                "kind": "for_entity",
+                "status": "success",
+                "verifier": "silicon",
+            },
+        })),
+        status(json!({
+            "msg_type": "verification_result",
+            "msg_body": {
+                "details": {
+                    "time": 80,
+                    "cached": true,
+                    "entity": entity("m", "method"),
+                    "result": { "errors": [error("Assertion false might not hold.")], "type": "error" },
+                },
+                "kind": "for_entity",
+                "status": "failure",
+                "verifier": "silicon",
+            },
+        })),
+        status(json!({
+            "msg_type": "verification_result",
+            "msg_body": {
+                "details": {
+                    "time": 300,
+                    "result": { "errors": [error("Assertion false might not hold.")], "type": "error" },
+                },
+                "kind": "overall",
+                "status": "failure",
+                "verifier": "silicon",
+            },
+        })),
+    ]);
+
+    assert!(!outcome.is_success());
+    assert_eq!(outcome.ast_success, Some(true));
+    assert_eq!(outcome.members.len(), 2);
+    assert_eq!(outcome.members_of_type("method").count(), 1);
+
+    let m = outcome.member("m").unwrap();
+    assert!(!m.success);
+    assert!(m.cached);
+    assert_eq!(m.time, Duration::from_millis(80));
+
+    assert_eq!(
+        outcome.overall.as_ref().unwrap().time,
+        Duration::from_millis(300)
+    );
+    assert_eq!(outcome.errors().count(), 1);
+    assert_eq!(outcome.messages.len(), 4);
+}
+
+#[test]
+fn typecheck_failure_and_exceptions_fail() {
+    let outcome = VerificationOutcome::from_statuses([status(json!({
+        "msg_type": "ast_construction_result",
+        "msg_body": {
+            "details": {
+                "time": 10,
+                "result": { "errors": [error("identifier x not defined.")], "type": "error" },
+            },
+            "status": "failure",
+        },
+    }))]);
+    assert!(!outcome.is_success());
+    assert_eq!(outcome.ast_errors.len(), 1);
+
+    let outcome = VerificationOutcome::from_statuses([status(json!({
+        "msg_type": "exception_report",
+        "msg_body": { "message": "boom", "stacktrace": [] },
+    }))]);
+    assert!(!outcome.is_success());
+    assert_eq!(outcome.exceptions[0].message, "boom");
+}
@@ -0,0 +1,29 @@
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::output::OutputLog;
+
+#[tokio::test]
+async fn output_log_is_bounded_and_followable() {
+    let log = OutputLog::new(3);
+    for i in 0..5 {
+        log.push(format!("line {i}"));
+    }
+    assert_eq!(log.recent(), ["line 2", "line 3", "line 4"]);
+    assert_eq!(log.tail(1), ["line 4"]);
+
+    let (recent, mut rx) = log.follow();
+    assert_eq!(recent.len(), 3);
+    // A dropped subscriber must not affect pushing
+    drop(log.subscribe());
+
+    log.push("line 5".to_string());
+    log.close();
+    log.closed().await;
+
+    assert_eq!(rx.recv().await.unwrap(), "line 5");
+    assert!(matches!(rx.recv().await, Err(RecvError::Closed)));
+    assert!(matches!(
+        log.subscribe().recv().await,
+        Err(RecvError::Closed)
+    ));
+}
@@ -0,0 +1,49 @@
+use std::{fs, sync::Barrier, thread};
+
+use crate::paths::extract_once;
+
+#[test]
+fn extract_once_writes_complete_files() -> color_eyre::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let target = dir.path().join("viperserver-0123");
+    let bytes = vec![7u8; 1 << 20];
+
+    let barrier = Barrier::new(8);
+    let paths = thread::scope(|s| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                s.spawn(|| {
+                    barrier.wait();
+                    extract_once(&target, "viperserver.jar", &bytes)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
+    let path = target.join("viperserver.jar");
+    assert!(paths.iter().all(|p| *p == path));
+    assert_eq!(fs::read(&path)?, bytes);
+    // Only the jar and the lock file, no temporary files left behind
+    assert_eq!(fs::read_dir(&target)?.count(), 2);
+    Ok(())
+}
+
+#[test]
+fn extract_once_replaces_truncated_files() -> color_eyre::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("viperserver.jar");
+    fs::write(&path, b"trunc")?;
+
+    extract_once(dir.path(), "viperserver.jar", b"truncated? no")?;
+    assert_eq!(fs::read(&path)?, b"truncated? no");
+
+    // A complete file is left alone
+    let modified = fs::metadata(&path)?.modified()?;
+    extract_once(dir.path(), "viperserver.jar", b"truncated? no")?;
+    assert_eq!(fs::metadata(&path)?.modified()?, modified);
+    Ok(())
+}
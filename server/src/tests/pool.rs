@@ -0,0 +1,61 @@
+use futures::StreamExt;
+
+use crate::{
+    client::{FlushCache, VerificationRequest},
+    error::ViperServerError,
+    jvm::JvmConfig,
+    pool::ServerPool,
+    server::ViperServer,
+    testing::{MockServer, Script},
+};
+
+#[tokio::test]
+async fn verifications_spread_across_instances() -> color_eyre::Result<()> {
+    let (a, b) = (MockServer::start()?, MockServer::start()?);
+    for server in [&a, &b] {
+        server.default_script(Script::verified());
+    }
+    let pool = ServerPool::from_clients([a.client().await?, b.client().await?]);
+
+    let request = VerificationRequest::silicon().verify_file("a.vpr")?;
+    let first = pool.post(request.clone()).await?;
+    let second = pool.post(request).await?;
+    assert_ne!(first.instance, second.instance);
+    assert_eq!(
+        pool.load().iter().map(|l| l.active).collect::<Vec<_>>(),
+        [1, 1]
+    );
+
+    let statuses: Vec<_> = pool.check_on_verification(&first).await?.collect().await;
+    assert_eq!(statuses.len(), 2);
+    drop(first);
+    assert_eq!(pool.load()[0].active + pool.load()[1].active, 1);
+
+    // Other requests are spread too, to the now idle instance, but do not
+    // count as in progress
+    let flush = pool.post(FlushCache).await?;
+    assert_ne!(flush.instance, second.instance);
+    let load = &pool.load()[flush.instance];
+    assert_eq!((load.active, load.assigned), (0, 1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn empty_pools_are_refused() -> color_eyre::Result<()> {
+    let err = ServerPool::spawn(
+        0,
+        &ViperServer::builder(),
+        &JvmConfig::default(),
+        "viperserver.jar",
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, ViperServerError::EmptyPool));
+
+    let pool = ServerPool::from_clients([]);
+    assert!(matches!(
+        pool.post(FlushCache).await,
+        Err(ViperServerError::EmptyPool)
+    ));
+    Ok(())
+}
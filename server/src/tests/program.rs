@@ -0,0 +1,54 @@
+#![cfg(feature = "silvers")]
+
+use silvers::{
+    expression::ExpR,
+    program::{Method, Program},
+    statement::Seqn,
+};
+
+use crate::{
+    client::VerificationRequest,
+    testing::{MockServer, Script},
+};
+
+#[tokio::test]
+async fn verify_a_silvers_program() -> color_eyre::Result<()> {
+    let program: Program<ExpR> = Program {
+        domains: vec![],
+        fields: vec![],
+        functions: vec![],
+        predicates: vec![],
+        methods: vec![Method {
+            name: "m".to_string(),
+            formal_args: vec![],
+            formal_returns: vec![],
+            pres: vec![],
+            posts: vec![],
+            body: Some(Seqn {
+                ss: vec![],
+                scoped_seqn_declarations: vec![],
+            }),
+        }],
+        extensions: vec![],
+    };
+    let request = VerificationRequest::silicon()
+        .timeout("10")
+        .verify_program(&program)?;
+    let VerificationRequest::Silicon { file, .. } = &request else {
+        unreachable!()
+    };
+    let path = file.path().to_path_buf();
+    assert_eq!(path.extension().unwrap(), "vpr");
+    assert_eq!(std::fs::read_to_string(&path)?, program.to_string());
+
+    let server = MockServer::start()?;
+    server.default_script(Script::verified());
+    let client = server.client().await?;
+    assert!(client.verify(request).await?.is_success());
+    assert_eq!(
+        server.requests(),
+        [["silicon", "--timeout", "10", path.to_str().unwrap()]]
+    );
+    assert!(!path.exists());
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use futures::{StreamExt, TryStreamExt};
+use serde_json::json;
+
+use crate::{
+    client::VerificationRequest,
+    progress::{Progress, ProgressEvent, ProgressTracker},
+    record::ReplayClient,
+    verification::VerificationStatus,
+};
+
+fn replay() -> color_eyre::Result<(ReplayClient, VerificationRequest)> {
+    let replay =
+        ReplayClient::from_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/fixtures"))?;
+    let request = VerificationRequest::silicon().verify_file("/tmp/fixtures/verified.vpr")?;
+    Ok((replay, request))
+}
+
+#[tokio::test]
+async fn progress_of_a_recorded_verification() -> color_eyre::Result<()> {
+    let (replay, request) = replay()?;
+
+    let mut tracker = ProgressTracker::new();
+    let mut events = vec![];
+    let mut statuses = replay.verify_stream(&request)?;
+    while let Some(status) = statuses.next().await {
+        events.extend(tracker.update(&status?));
+    }
+
+    assert_eq!(events[0], ProgressEvent::Parsed);
+    assert_eq!(events[1], ProgressEvent::Typechecked);
+    assert_eq!(events[2], ProgressEvent::Members { total: 2 });
+    assert!(matches!(
+        &events[3],
+        ProgressEvent::MemberFinished { index: 1, total: Some(2), entity, cached: false, .. }
+            if entity.name == "fib"
+    ));
+    assert!(matches!(
+        &events[4],
+        ProgressEvent::MemberFinished {
+            index: 2,
+            cached: true,
+            ..
+        }
+    ));
+    assert_eq!(
+        events[5],
+        ProgressEvent::Completed {
+            success: true,
+            time: Duration::from_millis(1021),
+            cached: 1,
+            fresh: 1,
+        }
+    );
+    assert_eq!(events.len(), 6);
+    assert_eq!(tracker.fraction(), Some(1.0));
+    assert_eq!(tracker.eta(), Some(Duration::ZERO));
+    Ok(())
+}
+
+#[tokio::test]
+async fn progress_stream_of_a_recorded_verification() -> color_eyre::Result<()> {
+    let (replay, request) = replay()?;
+
+    let mut tracker = ProgressTracker::new();
+    let mut expected = vec![];
+    let mut statuses = replay.verify_stream(&request)?;
+    while let Some(status) = statuses.next().await {
+        expected.extend(tracker.update(&status?));
+    }
+
+    let mut progress = Progress::new(replay.verify_stream(&request)?);
+    let mut events = vec![];
+    while let Some(event) = progress.try_next().await? {
+        events.push(event);
+    }
+    assert_eq!(events, expected);
+    assert!(progress.tracker().is_completed());
+    assert_eq!(progress.tracker().fraction(), Some(1.0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn outline_corrects_the_total_from_statistics() -> color_eyre::Result<()> {
+    let member = |name: &str, ty: &str| {
+        json!({
+            "name": name,
+            "position": { "file": "/tmp/a.vpr", "start": "1:1", "end": "2:1" },
+            "type": ty,
+        })
+    };
+    let statuses: Vec<VerificationStatus> = serde_json::from_value(json!([
+        {
+            "msg_type": "statistics",
+            "msg_body": { "domains": 0, "fields": 0, "functions": 1, "methods": 2, "predicates": 0 }
+        },
+        {
+            "msg_type": "program_outline",
+            "msg_body": { "members": [member("f", "function"), member("m", "method")] }
+        },
+        {
+            "msg_type": "verification_result",
+            "msg_body": {
+                "details": { "cached": false, "entity": member("f", "function"), "time": 10 },
+                "kind": "for_entity",
+                "status": "success",
+                "verifier": "silicon",
+            }
+        },
+        {
+            "msg_type": "verification_result",
+            "msg_body": {
+                "details": { "cached": false, "entity": member("m", "method"), "time": 10 },
+                "kind": "for_entity",
+                "status": "success",
+                "verifier": "silicon",
+            }
+        },
+    ]))?;
+
+    // One of the methods is abstract, and gets no result of its own
+    let mut progress = Progress::new(futures::stream::iter(statuses.into_iter().map(Ok)));
+    let mut totals = vec![];
+    while let Some(event) = progress.try_next().await? {
+        if let ProgressEvent::Members { total } = event {
+            totals.push(total);
+        }
+    }
+    assert_eq!(totals, [3, 2]);
+    assert_eq!(progress.tracker().fraction(), Some(1.0));
+    Ok(())
+}
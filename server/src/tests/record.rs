@@ -0,0 +1,74 @@
+use futures::{StreamExt, TryStreamExt};
+
+use crate::{
+    client::VerificationRequest,
+    record::{Fixture, ReplayClient},
+    testing::{MockServer, Script},
+    verification::VerificationStatus,
+};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/tests/fixtures");
+
+/// Every recorded line parses, and survives a round trip through
+/// serialization.
+#[test]
+fn fixture_corpus_deserializes() -> color_eyre::Result<()> {
+    let replay = ReplayClient::from_dir(FIXTURES)?;
+    assert_eq!(replay.fixtures().len(), 4);
+    for fixture in replay.fixtures() {
+        for recorded in &fixture.lines {
+            let status: VerificationStatus = serde_json::from_str(&recorded.line)
+                .map_err(|err| color_eyre::eyre::eyre!("{}: {err}", recorded.line))?;
+            let again = serde_json::from_value(serde_json::to_value(&status)?)?;
+            assert_eq!(status, again, "{}", recorded.line);
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_fixtures_by_request() -> color_eyre::Result<()> {
+    let replay = ReplayClient::from_dir(FIXTURES)?;
+
+    let verified = VerificationRequest::silicon().verify_file("/tmp/fixtures/verified.vpr")?;
+    let outcome = replay.verify(&verified).await?;
+    assert!(outcome.is_success());
+    assert_eq!(outcome.members.len(), 2);
+
+    let failing = VerificationRequest::carbon().verify_file("/tmp/fixtures/failing.vpr")?;
+    let outcome = replay.verify(&failing).await?;
+    assert!(!outcome.is_success());
+    assert_eq!(outcome.errors().count(), 1);
+
+    let unknown = VerificationRequest::silicon().verify_file("/tmp/unknown.vpr")?;
+    assert!(replay.verify(&unknown).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn record_and_replay() -> color_eyre::Result<()> {
+    let server = MockServer::start()?;
+    server.default_script(Script::failed([("tag", "text")]));
+    let client = server.client().await?;
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("recorded.ndjson");
+    let request = VerificationRequest::silicon().verify_file("/work/a b.vpr")?;
+    let recorded: Vec<_> = client
+        .verify_recorded(request.clone(), &path)
+        .await?
+        .try_collect()
+        .await?;
+
+    let fixture = Fixture::load(&path)?;
+    assert_eq!(fixture.command_line, "silicon \"/work/a b.vpr\"");
+    assert_eq!(fixture.lines.len(), recorded.len());
+
+    let replayed: Vec<_> = ReplayClient::new([fixture])
+        .verify_stream(&request)?
+        .collect()
+        .await;
+    let replayed = replayed.into_iter().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(replayed, recorded);
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use reqwest::Method;
+use serde_json::json;
+
+use crate::{
+    client::{Discard, FlushCache, FlushFileCache, SourceFile, VerificationRequest, ViperRequest},
+    error::ViperServerError,
+    verification::Backend,
+};
+
+#[test]
+fn request_endpoints_and_bodies() {
+    assert_eq!(FlushCache.method(), Method::GET);
+    assert_eq!(FlushCache.url(), "cache/flush");
+    assert_eq!(FlushCache.body().unwrap(), None);
+
+    let flush = FlushFileCache {
+        backend: Backend::Carbon,
+        file: "/tmp/a.vpr".into(),
+    };
+    assert_eq!(flush.method(), Method::POST);
+    assert_eq!(
+        flush.body().unwrap(),
+        Some(json!({ "backend": "carbon", "file": "/tmp/a.vpr" }))
+    );
+
+    assert_eq!(Discard { id: 7 }.url(), "discard/7");
+}
+
+#[test]
+fn staged_source_lives_as_long_as_the_request() {
+    let request = VerificationRequest::silicon()
+        .verify_source("method m() {}")
+        .unwrap();
+    let path = match &request {
+        VerificationRequest::Silicon { file, .. } => file.path().to_path_buf(),
+        _ => unreachable!(),
+    };
+    assert_eq!(path.file_name().unwrap(), SourceFile::DEFAULT_NAME);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "method m() {}");
+
+    let serialized = serde_json::to_value(&request).unwrap();
+    assert!(serialized
+        .as_str()
+        .unwrap()
+        .ends_with(path.to_str().unwrap()));
+
+    drop(request);
+    assert!(!path.exists());
+}
+
+#[test]
+fn staged_source_names_stay_in_the_staging_directory() {
+    let staged = SourceFile::stage("hello.vpr", "").unwrap();
+    assert_eq!(staged.path().file_name().unwrap(), "hello.vpr");
+
+    for name in ["../x.vpr", "/etc/x", "dir/x.vpr", "..", ".", ""] {
+        assert!(
+            matches!(
+                SourceFile::stage(name, ""),
+                Err(ViperServerError::InvalidSourceName { .. })
+            ),
+            "{name:?} was accepted"
+        );
+    }
+    assert!(VerificationRequest::carbon()
+        .verify_source_named("../x.vpr", "")
+        .is_err());
+}
+
+#[test]
+fn backends_round_trip_through_serde() {
+    for (json, backend) in [
+        ("\"silicon\"", Backend::Silicon),
+        ("\"carbon\"", Backend::Carbon),
+        (
+            "\"viper.gobra.Backend\"",
+            Backend::Custom("viper.gobra.Backend".to_string()),
+        ),
+    ] {
+        assert_eq!(serde_json::from_str::<Backend>(json).unwrap(), backend);
+        assert_eq!(serde_json::to_string(&backend).unwrap(), json);
+    }
+}
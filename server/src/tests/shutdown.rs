@@ -0,0 +1,71 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use command_group::AsyncCommandGroup;
+
+use super::fake_java;
+use crate::{
+    client::Client,
+    process::Process,
+    server::{ShutdownMethod, ViperServer},
+    testing::MockServer,
+};
+
+#[tokio::test]
+async fn process_group_is_killed_and_reaped() -> color_eyre::Result<()> {
+    let child = tokio::process::Command::new("sh")
+        .args(["-c", "sleep 60 & sleep 60"])
+        .group_spawn()?;
+    let process = Process::new(child);
+    assert!(process.try_status().is_none());
+
+    process.kill();
+    let status = tokio::time::timeout(Duration::from_secs(10), process.wait()).await??;
+    assert!(!status.success());
+    assert!(process.clone().try_status().is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn shutdown_escalates_until_the_server_exits() -> color_eyre::Result<()> {
+    let mock = MockServer::start()?;
+    let dir = tempfile::tempdir()?;
+    let online = format!("echo 'ViperServer online at {}'", mock.url());
+    let timeout = Duration::from_millis(300);
+
+    // Ignores the exit request, but not SIGTERM
+    let jvm = fake_java(
+        dir.path(),
+        &format!("{online}\necho bye >&2\nexec sleep 60"),
+    )?;
+    let server = ViperServer::builder()
+        .spawn_http_with(&jvm, "viperserver.jar")
+        .await?;
+    let report = Client::new(server).await?.shutdown(timeout).await?.unwrap();
+    assert!(mock.exited());
+    assert_eq!(report.method, ShutdownMethod::Terminated);
+    assert_eq!(report.stderr, ["bye"]);
+
+    // Ignores SIGTERM too
+    let jvm = fake_java(
+        dir.path(),
+        &format!("trap '' TERM\n{online}\nwhile true; do sleep 0.1; done"),
+    )?;
+    let mut server = ViperServer::builder()
+        .spawn_http_with(&jvm, "viperserver.jar")
+        .await?;
+    server.online_at().await?;
+    let report = server.shutdown(timeout).await?;
+    assert_eq!(report.method, ShutdownMethod::Killed);
+
+    let jvm = fake_java(dir.path(), &format!("{online}\nexit 3"))?;
+    let mut server = ViperServer::builder()
+        .spawn_http_with(&jvm, "viperserver.jar")
+        .await?;
+    server.wait().await?;
+    let report = server.shutdown(timeout).await?;
+    assert_eq!(report.method, ShutdownMethod::AlreadyExited);
+    assert_eq!(report.status.code(), Some(3));
+    Ok(())
+}
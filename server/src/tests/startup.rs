@@ -0,0 +1,104 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use super::fake_java;
+use crate::{
+    error::ViperServerError,
+    opts::ViperServerOptsBuilder,
+    server::{ServerConfig, ViperServer},
+};
+
+/// The server, and the directory its fake `java` lives in.
+async fn spawn(
+    script: &str,
+    startup_timeout: Duration,
+) -> color_eyre::Result<(ViperServer, tempfile::TempDir)> {
+    let dir = tempfile::tempdir()?;
+    let jvm = fake_java(dir.path(), script)?;
+    let config = ServerConfig::builder()
+        .startup_timeout(startup_timeout)
+        .build()?;
+    let opts = ViperServerOptsBuilder::default().build()?;
+    let server = ViperServer::spawn_configured("viperserver.jar", &jvm, &config, opts).await?;
+    Ok((server, dir))
+}
+
+#[tokio::test]
+async fn server_that_never_comes_online_is_killed() -> color_eyre::Result<()> {
+    let (mut server, _dir) =
+        spawn("echo starting\nexec sleep 60", Duration::from_millis(200)).await?;
+
+    match server.online_at().await {
+        Err(ViperServerError::ServerStartupTimeout { stdout, .. }) => {
+            assert_eq!(stdout, "starting")
+        }
+        res => panic!("expected a startup timeout, got {res:?}"),
+    }
+    let status = tokio::time::timeout(Duration::from_secs(10), server.wait()).await??;
+    assert!(!status.success());
+
+    assert!(matches!(
+        server.online_at().await,
+        Err(ViperServerError::ServerStartupFailed { .. })
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn server_that_exits_during_startup() -> color_eyre::Result<()> {
+    let (mut server, _dir) = spawn(
+        "echo 'Error: Unable to access jarfile' >&2\nexit 2",
+        Duration::from_secs(30),
+    )
+    .await?;
+
+    match server.online_at().await {
+        Err(ViperServerError::ServerExited { code, stderr, .. }) => {
+            assert_eq!(code, Some(2));
+            assert_eq!(stderr, "Error: Unable to access jarfile");
+        }
+        res => panic!("expected the server to exit, got {res:?}"),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn server_that_closes_stdout_without_announcing_itself() -> color_eyre::Result<()> {
+    let (mut server, _dir) = spawn("exec >&-\nexec sleep 60", Duration::from_secs(30)).await?;
+
+    assert!(matches!(
+        server.online_at().await,
+        Err(ViperServerError::ServerDidNotReportPort { .. })
+    ));
+    server.kill();
+    Ok(())
+}
+
+#[tokio::test]
+async fn output_that_is_not_utf8_is_still_drained() -> color_eyre::Result<()> {
+    // Far more than fits into a pipe buffer follows the first invalid lines
+    let (mut server, _dir) = spawn(
+        r#"printf 'caf\351\n'
+printf 'Fehler: \374berpr\374fung\n' >&2
+i=0
+while [ $i -lt 5000 ]; do
+  echo "line $i of output that fills the pipe"
+  echo "line $i of output that fills the pipe" >&2
+  i=$((i+1))
+done
+printf 'caf\351\n'
+printf 'Fehler: \374berpr\374fung\n' >&2
+echo 'ViperServer online at http://localhost:1/'
+exec sleep 60"#,
+        Duration::from_secs(10),
+    )
+    .await?;
+
+    assert_eq!(server.online_at().await?, "http://localhost:1/");
+    assert_eq!(server.stdout.tail(2)[0], "caf\u{FFFD}");
+    server.kill();
+    server.stderr.closed().await;
+    assert_eq!(server.stderr.tail(1), ["Fehler: \u{FFFD}berpr\u{FFFD}fung"]);
+    Ok(())
+}
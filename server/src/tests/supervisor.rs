@@ -0,0 +1,178 @@
+#![cfg(unix)]
+
+use std::time::Duration;
+
+use futures::TryStreamExt;
+use tokio::sync::broadcast::Receiver;
+
+use super::fake_java;
+use crate::{
+    client::{Exit, VerificationRequest},
+    opts::ViperServerOptsBuilder,
+    supervisor::{RestartEvent, RestartPolicy, Supervisor},
+    testing::{MockServer, Script},
+    verification::VerificationStatus,
+};
+
+fn policy() -> RestartPolicy {
+    RestartPolicy::builder()
+        .max_restarts(2)
+        .initial_backoff(Duration::from_millis(10))
+        .health_check_interval(Duration::from_millis(20))
+        .build()
+        .unwrap()
+}
+
+async fn next_event(events: &mut Receiver<RestartEvent>) -> RestartEvent {
+    tokio::time::timeout(Duration::from_secs(10), events.recv())
+        .await
+        .expect("no restart event in time")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn restart_until_the_limit() -> color_eyre::Result<()> {
+    let mock = MockServer::start()?;
+    let dir = tempfile::tempdir()?;
+    let jvm = fake_java(
+        dir.path(),
+        &format!("echo 'ViperServer online at {}'\nsleep 0.2", mock.url()),
+    )?;
+    let opts = ViperServerOptsBuilder::default().build()?;
+    let supervisor = Supervisor::spawn("viperserver.jar", &jvm, opts, policy()).await?;
+    let mut events = supervisor.subscribe();
+
+    let mut restarted = 0;
+    loop {
+        match next_event(&mut events).await {
+            RestartEvent::Restarted { .. } => restarted += 1,
+            RestartEvent::GaveUp { restarts } => {
+                assert_eq!(restarts, 2);
+                break;
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(restarted, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn restarts_are_forgotten_once_healthy() -> color_eyre::Result<()> {
+    let mock = MockServer::start()?;
+    let dir = tempfile::tempdir()?;
+    let started = dir.path().join("started");
+    // Only the first server exits
+    let jvm = fake_java(
+        dir.path(),
+        &format!(
+            "echo 'ViperServer online at {url}'\n\
+             if [ -e {started} ]; then exec sleep 60; fi\n\
+             touch {started}\nsleep 0.2",
+            url = mock.url(),
+            started = started.display(),
+        ),
+    )?;
+    let mut policy = policy();
+    policy.reset_after = Duration::from_millis(200);
+    let opts = ViperServerOptsBuilder::default().build()?;
+    let supervisor = Supervisor::spawn("viperserver.jar", &jvm, opts, policy).await?;
+    let mut events = supervisor.subscribe();
+
+    while !matches!(
+        next_event(&mut events).await,
+        RestartEvent::Restarted { .. }
+    ) {}
+    assert_eq!(supervisor.restarts(), 1);
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while supervisor.restarts() != 0 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn verification_continues_on_the_restarted_server() -> color_eyre::Result<()> {
+    let crashing = MockServer::start()?;
+    crashing.default_script(
+        Script::new()
+            .status(VerificationStatus::CopyrightReport {
+                text: "first server".to_string(),
+            })
+            .delay(Duration::from_millis(500))
+            .disconnect(),
+    );
+    let restarted = MockServer::start()?;
+    restarted.default_script(Script::verified());
+
+    let dir = tempfile::tempdir()?;
+    let started = dir.path().join("started");
+    // The first server exits while its verification is running
+    let jvm = fake_java(
+        dir.path(),
+        &format!(
+            "if [ -e {started} ]; then
+             echo 'ViperServer online at {restarted}'
+exec sleep 60
+fi
+             touch {started}
+echo 'ViperServer online at {crashing}'
+sleep 0.2",
+            started = started.display(),
+            restarted = restarted.url(),
+            crashing = crashing.url(),
+        ),
+    )?;
+    let opts = ViperServerOptsBuilder::default().build()?;
+    let supervisor = Supervisor::spawn("viperserver.jar", &jvm, opts, policy()).await?;
+
+    let request = VerificationRequest::silicon().verify_file("/tmp/a.vpr")?;
+    let statuses: Vec<_> = tokio::time::timeout(
+        Duration::from_secs(10),
+        supervisor.verify(request).try_collect(),
+    )
+    .await??;
+
+    assert!(matches!(
+        &statuses[0],
+        VerificationStatus::CopyrightReport { text } if text == "first server"
+    ));
+    assert!(matches!(
+        statuses.last(),
+        Some(VerificationStatus::VerificationResult { status, .. }) if status == "success"
+    ));
+    assert_eq!(crashing.requests().len(), 1);
+    assert_eq!(restarted.requests().len(), 1);
+    assert_eq!(supervisor.restarts(), 1);
+    assert_eq!(supervisor.base_url().as_str(), restarted.url());
+    Ok(())
+}
+
+#[tokio::test]
+async fn requests_without_a_job_are_not_sent_again() -> color_eyre::Result<()> {
+    let mock = MockServer::start()?;
+    let dir = tempfile::tempdir()?;
+    let jvm = fake_java(
+        dir.path(),
+        &format!(
+            "echo 'ViperServer online at {}'
+exec sleep 60",
+            mock.url()
+        ),
+    )?;
+    let mut policy = policy();
+    policy.health_check_interval = Duration::from_secs(60);
+    let opts = ViperServerOptsBuilder::default().build()?;
+    let supervisor = Supervisor::spawn("viperserver.jar", &jvm, opts, policy).await?;
+    let mut events = supervisor.subscribe();
+
+    // The server stops answering, but an exit request is not worth a restart
+    drop(mock);
+    assert!(supervisor.post(Exit).await.is_err());
+    assert!(events.try_recv().is_err());
+    assert_eq!(supervisor.restarts(), 0);
+    Ok(())
+}
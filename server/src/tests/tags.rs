@@ -0,0 +1,95 @@
+use serde_json::json;
+
+use crate::verification::{
+    DetailsError, ErrorKind, ErrorReason, ErrorTag, OptionalPosition, VerificationStatus,
+};
+
+#[test]
+fn parse_known_and_unknown_tags() {
+    let tag = ErrorTag::parse("call.precondition:insufficient.permission");
+    assert_eq!(tag.kind, ErrorKind::PreconditionInCallFalse);
+    assert_eq!(tag.reason, Some(ErrorReason::InsufficientPermission));
+
+    let tag = ErrorTag::parse("parser.error");
+    assert_eq!(tag.kind, ErrorKind::ParseError);
+    assert_eq!(tag.reason, None);
+
+    let tag = ErrorTag::parse("custom.check:something.odd");
+    assert_eq!(tag.kind, ErrorKind::Other("custom.check".to_string()));
+    assert_eq!(
+        tag.reason,
+        Some(ErrorReason::Other("something.odd".to_string()))
+    );
+
+    for raw in [
+        "assert.failed:assertion.false",
+        "fold.failed:division.by.zero",
+        "custom.check:something.odd",
+        "a:b:c",
+        "typechecker.error",
+        "",
+    ] {
+        let error = DetailsError {
+            cached: false,
+            position: OptionalPosition::None,
+            tag: raw.into(),
+            text: String::new(),
+        };
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["tag"], raw);
+        assert_eq!(
+            serde_json::from_value::<DetailsError>(value).unwrap(),
+            error
+        );
+        assert_eq!(error.tag, raw);
+    }
+}
+
+#[test]
+fn filter_errors_by_kind() {
+    let error = |tag: &str| {
+        json!({
+            "cached": false,
+            "position": "<no position>",
+            "tag": tag,
+            "text": "",
+        })
+    };
+    let status: VerificationStatus = serde_json::from_value(json!({
+        "msg_type": "verification_result",
+        "msg_body": {
+            "details": {
+                "time": 10,
+                "result": {
+                    "type": "error",
+                    "errors": [
+                        error("postcondition.violated:assertion.false"),
+                        error("assert.failed:insufficient.permission"),
+                        error("postcondition.violated:insufficient.permission"),
+                    ],
+                },
+            },
+            "kind": "overall",
+            "status": "failure",
+            "verifier": "silicon",
+        },
+    }))
+    .unwrap();
+
+    assert_eq!(
+        status
+            .errors_of_kind(&ErrorKind::PostconditionViolated)
+            .count(),
+        2
+    );
+    assert_eq!(status.errors_of_kind(&ErrorKind::FoldFailed).count(), 0);
+    let reason = ErrorReason::InsufficientPermission;
+    let kinds: Vec<_> = status
+        .errors_with_reason(&reason)
+        .map(|e| &e.tag.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [&ErrorKind::AssertFailed, &ErrorKind::PostconditionViolated]
+    );
+}
@@ -0,0 +1,38 @@
+use crate::toolchain::{self, Tool, ToolSource, ToolchainError, Version};
+
+#[test]
+fn find_versions() {
+    assert_eq!(
+        Version::find("Z3 version 4.8.7 - 64 bit"),
+        Some(Version(vec![4, 8, 7]))
+    );
+    assert_eq!(
+        Version::find("Boogie program verifier version 2.15.7.0, Copyright (c) 2003-2014."),
+        Some(Version(vec![2, 15, 7, 0]))
+    );
+    assert_eq!(Version::find("command not found"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn check_tool_versions() -> color_eyre::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir()?;
+    let fake_z3 = |name: &str, version: &str| -> std::io::Result<_> {
+        let path = dir.path().join(name);
+        std::fs::write(
+            &path,
+            format!("#!/bin/sh\necho 'Z3 version {version} - 64 bit'\n"),
+        )?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(path)
+    };
+
+    let z3 = toolchain::check(Tool::Z3, fake_z3("new", "4.12.1")?, ToolSource::Path)?;
+    assert_eq!(z3.version.to_string(), "4.12.1");
+
+    let err = toolchain::check(Tool::Z3, fake_z3("old", "4.4.0")?, ToolSource::Path).unwrap_err();
+    assert!(matches!(err, ToolchainError::UnsupportedVersion { .. }));
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use thiserror::Error;
+
+use crate::paths;
+
+/// A prover the backends run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tool {
+    /// Used by Silicon directly and by Carbon through Boogie.
+    Z3,
+    /// Used by Carbon.
+    Boogie,
+}
+
+impl Tool {
+    /// The environment variable pointing at the executable, as understood by
+    /// the backends themselves.
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Tool::Z3 => "Z3_EXE",
+            Tool::Boogie => "BOOGIE_EXE",
+        }
+    }
+
+    /// The oldest version the backends support.
+    pub fn minimum_version(self) -> Version {
+        match self {
+            Tool::Z3 => Version(vec![4, 5, 0]),
+            Tool::Boogie => Version(vec![2, 4, 0]),
+        }
+    }
+
+    fn executable_names(self) -> &'static [&'static str] {
+        match (self, cfg!(windows)) {
+            (Tool::Z3, false) => &["z3"],
+            (Tool::Z3, true) => &["z3.exe"],
+            (Tool::Boogie, false) => &["boogie", "Boogie"],
+            (Tool::Boogie, true) => &["boogie.exe", "Boogie.exe"],
+        }
+    }
+
+    fn version_arg(self) -> &'static str {
+        match self {
+            Tool::Z3 => "--version",
+            Tool::Boogie => "/version",
+        }
+    }
+
+    /// Where the tool is commonly installed, besides `PATH`.
+    fn prefixes(self) -> Vec<PathBuf> {
+        let mut prefixes: Vec<PathBuf> = ["/usr/local/bin", "/opt/homebrew/bin", "/usr/bin"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        if let (Tool::Boogie, Some(home)) = (self, paths::home_dir()) {
+            // `dotnet tool install --global boogie`
+            prefixes.push(home.join(".dotnet").join("tools"));
+        }
+        prefixes.extend(
+            paths::viper_tools_dirs()
+                .into_iter()
+                .map(|tools| match self {
+                    Tool::Z3 => tools.join("z3").join("bin"),
+                    Tool::Boogie => tools.join("boogie").join("Binaries"),
+                }),
+        );
+        prefixes
+    }
+}
+
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tool::Z3 => write!(f, "Z3"),
+            Tool::Boogie => write!(f, "Boogie"),
+        }
+    }
+}
+
+/// A dotted version number, such as `4.8.7`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version(pub Vec<u32>);
+
+impl Version {
+    /// Find the version in the output of `z3 --version` or `boogie /version`,
+    /// such as `Z3 version 4.8.7 - 64 bit`.
+    pub fn find(output: &str) -> Option<Version> {
+        output
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter(|word| word.contains('.'))
+            .find_map(|word| {
+                word.trim_matches('.')
+                    .split('.')
+                    .map(|part| part.parse().ok())
+                    .collect::<Option<Vec<u32>>>()
+            })
+            .map(Version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, part) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{part}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Where a tool was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolSource {
+    /// The tool's environment variable, see [`Tool::env_var`].
+    Env,
+    Path,
+    /// A common install location, including the Viper IDE's tools.
+    Prefix,
+}
+
+/// A tool executable whose version was checked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Located {
+    pub tool: Tool,
+    pub path: PathBuf,
+    pub version: Version,
+    pub source: ToolSource,
+}
+
+#[derive(Debug, Error)]
+pub enum ToolchainError {
+    #[error(
+        "could not find {tool}, set {} or add it to PATH. looked at: {}",
+        tool.env_var(),
+        searched.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    NotFound { tool: Tool, searched: Vec<PathBuf> },
+    #[error("{} points to `{path}`, which does not exist", tool.env_var())]
+    EnvPathMissing { tool: Tool, path: PathBuf },
+    #[error("failed to run `{path}` to determine the {tool} version")]
+    Run {
+        tool: Tool,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("could not determine the {tool} version of `{path}` from: {output:?}")]
+    UnrecognizedVersion {
+        tool: Tool,
+        path: PathBuf,
+        output: String,
+    },
+    #[error("`{path}` is {tool} {found}, but the backends require {minimum} or newer")]
+    UnsupportedVersion {
+        tool: Tool,
+        path: PathBuf,
+        found: Version,
+        minimum: Version,
+    },
+}
+
+/// Find `tool`, looking at its environment variable, `PATH` and then common
+/// install locations, and check that its version is supported.
+///
+/// An executable named by the environment variable is never passed over: if it
+/// is missing or unsupported, that is reported rather than looking further.
+pub fn find(tool: Tool) -> Result<Located, ToolchainError> {
+    if let Some(path) = std::env::var_os(tool.env_var()).filter(|p| !p.is_empty()) {
+        let path = PathBuf::from(path);
+        if !path.is_file() {
+            return Err(ToolchainError::EnvPathMissing { tool, path });
+        }
+        return check(tool, path, ToolSource::Env);
+    }
+
+    let mut searched = vec![];
+    let path_dirs = std::env::var_os("PATH")
+        .map(|p| std::env::split_paths(&p).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let candidates = path_dirs
+        .into_iter()
+        .map(|dir| (dir, ToolSource::Path))
+        .chain(
+            tool.prefixes()
+                .into_iter()
+                .map(|dir| (dir, ToolSource::Prefix)),
+        );
+
+    let mut first_error = None;
+    for (dir, source) in candidates {
+        for name in tool.executable_names() {
+            let path = dir.join(name);
+            if !path.is_file() {
+                if source == ToolSource::Prefix {
+                    searched.push(path);
+                }
+                continue;
+            }
+            // Keep looking past unsupported versions, but report the first
+            // one if nothing better turns up
+            match check(tool, path, source) {
+                Ok(located) => return Ok(located),
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+    }
+
+    Err(first_error.unwrap_or(ToolchainError::NotFound { tool, searched }))
+}
+
+/// Run `path` to determine its version, and check it against
+/// [`Tool::minimum_version`].
+pub fn check(tool: Tool, path: PathBuf, source: ToolSource) -> Result<Located, ToolchainError> {
+    let version = version(tool, &path)?;
+    let minimum = tool.minimum_version();
+    if version < minimum {
+        return Err(ToolchainError::UnsupportedVersion {
+            tool,
+            path,
+            found: version,
+            minimum,
+        });
+    }
+    Ok(Located {
+        tool,
+        path,
+        version,
+        source,
+    })
+}
+
+fn version(tool: Tool, path: &Path) -> Result<Version, ToolchainError> {
+    let output = Command::new(path)
+        .arg(tool.version_arg())
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|source| ToolchainError::Run {
+            tool,
+            path: path.to_path_buf(),
+            source,
+        })?;
+    let output = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Version::find(&output).ok_or_else(|| ToolchainError::UnrecognizedVersion {
+        tool,
+        path: path.to_path_buf(),
+        output,
+    })
+}
@@ -3,16 +3,58 @@ use std::{collections::HashMap, marker::PhantomData, str::FromStr};
 use serde::{de, Deserialize, Serialize};
 use thiserror::Error;
 
+mod tag;
+pub use tag::*;
+
 #[derive(
-    Debug, derive_more::Display, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq,
+    Debug, derive_more::Display, Default, Clone, Serialize, Deserialize, PartialEq, Eq, Hash,
 )]
-#[serde(rename_all = "snake_case")]
+#[serde(from = "String", into = "String")]
 pub enum Backend {
     #[display(fmt = "carbon")]
     Carbon,
     #[default]
     #[display(fmt = "silicon")]
     Silicon,
+    /// Any other backend, by the name the server knows it by, such as the
+    /// class name of a custom backend.
+    #[display(fmt = "{_0}")]
+    Custom(String),
+}
+
+impl Backend {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Backend::Carbon => "carbon",
+            Backend::Silicon => "silicon",
+            Backend::Custom(name) => name,
+        }
+    }
+}
+
+impl From<String> for Backend {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "carbon" => Backend::Carbon,
+            "silicon" => Backend::Silicon,
+            _ => Backend::Custom(name),
+        }
+    }
+}
+
+impl From<&str> for Backend {
+    fn from(name: &str) -> Self {
+        name.to_string().into()
+    }
+}
+
+impl From<Backend> for String {
+    fn from(backend: Backend) -> Self {
+        match backend {
+            Backend::Custom(name) => name,
+            backend => backend.as_str().to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -79,6 +121,9 @@ pub enum VerificationStatus {
     VerificationTerminationMessage {
         msg: String,
     },
+    /// Not sent by the server, but ends the stream of a verification that was
+    /// cancelled by the client.
+    Cancelled,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Details {
@@ -110,17 +155,27 @@ pub struct DetailsError {
     pub cached: bool,
     #[serde(deserialize_with = "string_or_struct")]
     pub position: OptionalPosition,
-    pub tag: String,
+    pub tag: ErrorTag,
     pub text: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum OptionalPosition {
     Some(Position),
     None,
 }
 
+impl Serialize for OptionalPosition {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // As sent by the server, so that statuses survive a round trip
+        match self {
+            OptionalPosition::Some(position) => position.serialize(serializer),
+            OptionalPosition::None => serializer.serialize_str("<no position>"),
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 #[error("expected \"<no position>\" or \"<undefined>\" found {found:?}")]
 pub struct ParseOptionalError {
@@ -247,4 +302,23 @@ impl VerificationStatus {
             .flat_map(|d| &d.result)
             .flat_map(|res| &res.errors)
     }
+
+    /// The errors of this status of the given kind, such as every
+    /// [`ErrorKind::PostconditionViolated`].
+    pub fn errors_of_kind<'a>(
+        &'a self,
+        kind: &'a ErrorKind,
+    ) -> impl Iterator<Item = &'a DetailsError> {
+        self.detail_errors().filter(move |e| e.tag.kind == *kind)
+    }
+
+    /// The errors of this status for the given reason, such as every
+    /// [`ErrorReason::InsufficientPermission`].
+    pub fn errors_with_reason<'a>(
+        &'a self,
+        reason: &'a ErrorReason,
+    ) -> impl Iterator<Item = &'a DetailsError> {
+        self.detail_errors()
+            .filter(move |e| e.tag.reason.as_ref() == Some(reason))
+    }
 }
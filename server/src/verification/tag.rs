@@ -0,0 +1,163 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Defines an enum of Viper's dotted identifiers, with an `Other` variant
+/// that keeps unknown identifiers as they are.
+macro_rules! identifiers {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $id:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// An identifier this crate does not know.
+            Other(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $id,)*
+                    Self::Other(id) => id,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                match id {
+                    $($id => Self::$variant,)*
+                    _ => Self::Other(id.to_string()),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+identifiers! {
+    /// What failed, the part of an [`ErrorTag`] before the `:`.
+    pub enum ErrorKind {
+        Internal = "internal",
+        AssignmentFailed = "assignment.failed",
+        CallFailed = "call.failed",
+        ContractNotWellformed = "contract.not.wellformed",
+        PreconditionInCallFalse = "call.precondition",
+        PreconditionInAppFalse = "application.precondition",
+        ExhaleFailed = "exhale.failed",
+        InhaleFailed = "inhale.failed",
+        IfFailed = "if.failed",
+        WhileFailed = "while.failed",
+        AssertFailed = "assert.failed",
+        TerminationFailed = "termination.failed",
+        PostconditionViolated = "postcondition.violated",
+        FoldFailed = "fold.failed",
+        UnfoldFailed = "unfold.failed",
+        PackageFailed = "package.failed",
+        ApplyFailed = "apply.failed",
+        LoopInvariantNotPreserved = "invariant.not.preserved",
+        LoopInvariantNotEstablished = "invariant.not.established",
+        FunctionNotWellformed = "function.not.wellformed",
+        PredicateNotWellformed = "predicate.not.wellformed",
+        MagicWandNotWellformed = "wand.not.wellformed",
+        HeuristicsFailed = "heuristics.failed",
+        ParseError = "parser.error",
+        TypecheckerError = "typechecker.error",
+        ConsistencyError = "consistency.error",
+        TimeoutOccurred = "timeout.occurred",
+    }
+}
+
+identifiers! {
+    /// Why it failed, the part of an [`ErrorTag`] after the `:`.
+    pub enum ErrorReason {
+        Internal = "internal",
+        FeatureUnsupported = "feature.unsupported",
+        UnexpectedNode = "unexpected.node",
+        AssertionFalse = "assertion.false",
+        ReceiverNull = "receiver.null",
+        DivisionByZero = "division.by.zero",
+        NegativePermission = "negative.permission",
+        InsufficientPermission = "insufficient.permission",
+        InvalidPermMultiplication = "invalid.perm.multiplication",
+        MagicWandChunkNotFound = "wand.not.found",
+        ReceiverNotInjective = "receiver.not.injective",
+        LabelledStateNotReached = "labelled.state.not.reached",
+        SeqIndexNegative = "seq.index.negative",
+        SeqIndexExceedsLength = "seq.index.length",
+        MapKeyNotContained = "map.key.contains",
+    }
+}
+
+/// The identifier of an error, such as `assert.failed:assertion.false`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub struct ErrorTag {
+    pub kind: ErrorKind,
+    /// Missing for errors without a reason, such as parse errors.
+    pub reason: Option<ErrorReason>,
+}
+
+impl ErrorTag {
+    pub fn parse(tag: &str) -> ErrorTag {
+        match tag.split_once(':') {
+            Some((kind, reason)) => ErrorTag {
+                kind: kind.into(),
+                reason: Some(reason.into()),
+            },
+            None => ErrorTag {
+                kind: tag.into(),
+                reason: None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ErrorTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            Some(reason) => write!(f, "{}:{reason}", self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl From<String> for ErrorTag {
+    fn from(tag: String) -> Self {
+        ErrorTag::parse(&tag)
+    }
+}
+
+impl From<&str> for ErrorTag {
+    fn from(tag: &str) -> Self {
+        ErrorTag::parse(tag)
+    }
+}
+
+impl From<ErrorTag> for String {
+    fn from(tag: ErrorTag) -> Self {
+        tag.to_string()
+    }
+}
+
+impl PartialEq<str> for ErrorTag {
+    fn eq(&self, other: &str) -> bool {
+        *self == ErrorTag::parse(other)
+    }
+}
+
+impl PartialEq<&str> for ErrorTag {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
@@ -478,6 +478,7 @@ impl<E> Exp<E> {
     }
 }
 
+#[allow(clippy::should_implement_trait)]
 impl ExpR {
     pub fn boolean(b: bool) -> Self {
         Exp::Literal(Literal::Boolean(b)).into()